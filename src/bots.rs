@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use crate::dto::{Direction, GameConfig, GameState, GameState_Coord, GameState_Snake, PlayerType};
+use crate::dto::NodeRole::VIEWER;
+
+const DIRECTIONS: [Direction; 4] = [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT];
+
+pub trait Strategy: Send {
+    // Called for every robot before any of them chooses, so slow strategies can think at the same time
+    fn prepare(&self, _state: &GameState, _config: &GameConfig, _player_id: i32) {}
+
+    // None keeps the snake going in its current direction
+    fn choose_direction(&self, state: &GameState, config: &GameConfig, player_id: i32) -> Option<Direction>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn strategy(&self) -> Box<dyn Strategy> {
+        match self {
+            Difficulty::Easy => Box::new(RandomSafe),
+            Difficulty::Medium => Box::new(Greedy),
+            Difficulty::Hard => Box::new(FloodFill),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("unknown robot difficulty {}, expected easy, medium or hard", s)),
+        }
+    }
+}
+
+// Either a number of hard robots or their difficulties, e.g. "easy,hard,hard"
+pub fn parse_robots(arg: &str) -> Result<Vec<Difficulty>, String> {
+    if let Ok(count) = arg.parse::<usize>() {
+        return Ok(vec![Difficulty::Hard; count]);
+    }
+    arg.split(',').map(|level| level.trim().parse()).collect()
+}
+
+// Robot names end with their level, e.g. "Robot 2 (easy)"
+fn difficulty_from_name(name: &str) -> Option<Difficulty> {
+    name.strip_suffix(')')?.rsplit_once('(')?.1.parse().ok()
+}
+
+type SharedStrategy = Arc<Mutex<Box<dyn Strategy>>>;
+
+// The robots driven by this node, they have no address of their own
+#[derive(Default)]
+pub struct Robots {
+    strategies: HashMap<i32, SharedStrategy>,
+}
+
+impl Robots {
+    // A deputy taking over the game drives the robots of the previous master on the same levels.
+    // External robots can not be carried over, their processes ran next to the previous master.
+    pub fn inherit(state: &GameState) -> Robots {
+        let strategies = state.get_players().get_players().iter()
+            .filter(|p| p.get_field_type() == PlayerType::ROBOT && p.is_local())
+            .filter_map(|p| Some((p.get_id(), Arc::new(Mutex::new(difficulty_from_name(p.get_name())?.strategy())))))
+            .collect();
+        Robots { strategies }
+    }
+
+    pub fn add(&mut self, state: &mut GameState, difficulty: Difficulty, config: &GameConfig) -> i32 {
+        self.add_with_strategy(state, difficulty.name(), difficulty.strategy(), config)
+    }
+
+    pub fn add_with_strategy(&mut self, state: &mut GameState, label: &str, strategy: Box<dyn Strategy>, config: &GameConfig) -> i32 {
+        let number = state.get_players().get_players().iter().filter(|p| p.get_field_type() == PlayerType::ROBOT).count() + 1;
+        let id = state.add_local_player(format!("Robot {} ({})", number, label), PlayerType::ROBOT, config);
+        self.strategies.insert(id, Arc::new(Mutex::new(strategy)));
+        id
+    }
+
+    pub fn remove(&mut self, state: &mut GameState) -> Option<i32> {
+        let id = state.remove_robot()?;
+        self.strategies.remove(&id);
+        Some(id)
+    }
+
+    // The strategies of the robots still in the game, steering with them does not need the robots any more
+    pub fn playing(&self, state: &GameState) -> PlayingRobots {
+        let strategies = state.get_players().get_players().iter()
+            .filter(|p| p.get_field_type() == PlayerType::ROBOT && p.is_local() && p.get_role() != VIEWER)
+            // inherited external robots play on the hardest level
+            .map(|p| (p.get_id(), self.strategies.get(&p.get_id()).cloned()
+                .unwrap_or_else(|| Arc::new(Mutex::new(Box::new(FloodFill))))))
+            .collect();
+        PlayingRobots { strategies }
+    }
+}
+
+pub struct PlayingRobots {
+    strategies: Vec<(i32, SharedStrategy)>,
+}
+
+impl PlayingRobots {
+    pub fn steer(&self, state: &GameState, config: &GameConfig, moves: &mut HashMap<i32, Direction>) {
+        for (player_id, strategy) in &self.strategies {
+            strategy.lock().expect("a robot strategy panicked").prepare(state, config, *player_id);
+        }
+        for (player_id, strategy) in &self.strategies {
+            if let Some(dir) = strategy.lock().expect("a robot strategy panicked").choose_direction(state, config, *player_id) {
+                moves.insert(*player_id, dir);
+            }
+        }
+    }
+}
+
+// Easy: any move that does not hit something right away
+pub struct RandomSafe;
+
+impl Strategy for RandomSafe {
+    fn choose_direction(&self, state: &GameState, config: &GameConfig, player_id: i32) -> Option<Direction> {
+        let snake = find_snake(state, player_id)?;
+        let occupied = occupied_cells(state, config);
+        let free = free_moves(snake, &occupied, config);
+        free.choose(&mut thread_rng()).map(|(dir, _)| *dir)
+    }
+}
+
+// Medium: the free move that gets closest to some food, without looking further ahead
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn choose_direction(&self, state: &GameState, config: &GameConfig, player_id: i32) -> Option<Direction> {
+        let snake = find_snake(state, player_id)?;
+        let occupied = occupied_cells(state, config);
+        free_moves(snake, &occupied, config).into_iter()
+            .min_by_key(|(_, next)| state.get_foods().iter()
+                .map(|food| wrapped_distance(next, food, config))
+                .min()
+                .unwrap_or(0))
+            .map(|(dir, _)| dir)
+    }
+}
+
+// Hard: heads to the nearest reachable food, but only through moves that leave enough room for the whole snake
+pub struct FloodFill;
+
+impl Strategy for FloodFill {
+    fn choose_direction(&self, state: &GameState, config: &GameConfig, player_id: i32) -> Option<Direction> {
+        let (width, height) = (config.get_width(), config.get_height());
+        let snake = find_snake(state, player_id)?;
+        let head = snake.get_head_position();
+        let occupied = occupied_cells(state, config);
+        let contested = contested_cells(state, config, player_id);
+
+        let mut safe = Vec::new();
+        let mut fallback = Vec::new();
+        for (dir, next) in free_moves(snake, &occupied, config) {
+            let room = reachable_area(&next, &occupied, width, height);
+            if room >= snake.get_length() as usize && !contested.contains(&(next.get_x(), next.get_y())) {
+                safe.push((dir, room));
+            } else {
+                fallback.push((dir, room));
+            }
+        }
+
+        let food = state.get_foods().iter().map(|f| (f.get_x(), f.get_y())).collect::<HashSet<_>>();
+        if let Some(dir) = first_step_to_food(head, &safe, &food, &occupied, width, height) {
+            return Some(dir);
+        }
+
+        // no food to go for, survive as long as possible
+        safe.into_iter().chain(fallback).max_by_key(|(_, room)| *room).map(|(dir, _)| dir)
+    }
+}
+
+fn find_snake(state: &GameState, player_id: i32) -> Option<&GameState_Snake> {
+    state.get_snakes().iter().find(|s| s.get_player_id() == player_id)
+}
+
+// Moves to cells nobody is standing on, turning back is never allowed
+fn free_moves(snake: &GameState_Snake, occupied: &HashSet<(i32, i32)>, config: &GameConfig) -> Vec<(Direction, GameState_Coord)> {
+    DIRECTIONS.iter()
+        .filter(|dir| **dir != snake.get_head_direction().opposite())
+        .map(|dir| (*dir, snake.get_head_position().next_with_dir(*dir, config.get_width(), config.get_height())))
+        .filter(|(_, next)| !occupied.contains(&(next.get_x(), next.get_y())))
+        .collect()
+}
+
+// Manhattan distance on the playing field, which wraps around inside the border
+fn wrapped_distance(a: &GameState_Coord, b: &GameState_Coord, config: &GameConfig) -> i32 {
+    let dx = (a.get_x() - b.get_x()).abs();
+    let dy = (a.get_y() - b.get_y()).abs();
+    dx.min(config.get_width() - 2 - dx) + dy.min(config.get_height() - 2 - dy)
+}
+
+fn occupied_cells(state: &GameState, config: &GameConfig) -> HashSet<(i32, i32)> {
+    state.get_snakes().iter()
+        .flat_map(|s| s.get_cells(config.get_width(), config.get_height()))
+        .map(|c| (c.get_x(), c.get_y()))
+        .collect()
+}
+
+// Cells other snakes may move their heads into on this turn
+fn contested_cells(state: &GameState, config: &GameConfig, player_id: i32) -> HashSet<(i32, i32)> {
+    state.get_snakes().iter()
+        .filter(|s| s.get_player_id() != player_id)
+        .flat_map(|s| DIRECTIONS.map(|dir| s.get_head_position().next_with_dir(dir, config.get_width(), config.get_height())))
+        .map(|c| (c.get_x(), c.get_y()))
+        .collect()
+}
+
+fn reachable_area(start: &GameState_Coord, occupied: &HashSet<(i32, i32)>, width: i32, height: i32) -> usize {
+    let mut visited = HashSet::from([(start.get_x(), start.get_y())]);
+    let mut queue = VecDeque::from([start.clone()]);
+
+    while let Some(cur) = queue.pop_front() {
+        for dir in DIRECTIONS {
+            let next = cur.next_with_dir(dir, width, height);
+            let key = (next.get_x(), next.get_y());
+            if !occupied.contains(&key) && visited.insert(key) {
+                queue.push_back(next);
+            }
+        }
+    }
+    visited.len()
+}
+
+// Breadth-first search that remembers which of the first moves every cell was reached with
+fn first_step_to_food(
+    head: &GameState_Coord,
+    first_steps: &[(Direction, usize)],
+    food: &HashSet<(i32, i32)>,
+    occupied: &HashSet<(i32, i32)>,
+    width: i32,
+    height: i32,
+) -> Option<Direction> {
+    let mut visited = HashSet::from([(head.get_x(), head.get_y())]);
+    let mut queue = VecDeque::new();
+
+    for (dir, _) in first_steps {
+        let next = head.next_with_dir(*dir, width, height);
+        if visited.insert((next.get_x(), next.get_y())) {
+            queue.push_back((next, *dir));
+        }
+    }
+
+    while let Some((cur, first)) = queue.pop_front() {
+        if food.contains(&(cur.get_x(), cur.get_y())) {
+            return Some(first);
+        }
+        for dir in DIRECTIONS {
+            let next = cur.next_with_dir(dir, width, height);
+            let key = (next.get_x(), next.get_y());
+            if !occupied.contains(&key) && visited.insert(key) {
+                queue.push_back((next, first));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake(id: i32, points: &[(i32, i32)], heading: Direction) -> GameState_Snake {
+        let mut snake = GameState_Snake::new_custom(points[0].0, points[0].1, id);
+        snake.mut_points().clear();
+        for (x, y) in points {
+            snake.mut_points().push(GameState_Coord::new_custom(*x, *y));
+        }
+        snake.set_head_direction(heading);
+        snake
+    }
+
+    fn game(snakes: Vec<GameState_Snake>, foods: &[(i32, i32)]) -> GameState {
+        let mut state = GameState::default();
+        state.mut_snakes().extend(snakes);
+        for (x, y) in foods {
+            state.mut_foods().push(GameState_Coord::new_custom(*x, *y));
+        }
+        state
+    }
+
+    // Robot 1 of length 4 with its head at (5, 10) below a pocket of two cells holding the food,
+    // walled in by two other snakes whose heads are far from it
+    fn pocket_game() -> GameState {
+        game(vec![
+            snake(1, &[(5, 10), (-3, 0)], Direction::RIGHT),
+            snake(2, &[(5, 5), (0, 2), (-1, 0), (0, 2)], Direction::UP),
+            snake(3, &[(6, 6), (0, 3)], Direction::UP),
+        ], &[(5, 8)])
+    }
+
+    #[test]
+    fn robot_levels_are_parsed() {
+        assert_eq!(parse_robots("2"), Ok(vec![Difficulty::Hard, Difficulty::Hard]));
+        assert_eq!(parse_robots("easy, medium,hard"), Ok(vec![Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]));
+        assert!(parse_robots("easy,insane").is_err());
+        assert!(parse_robots("").is_err());
+    }
+
+    #[test]
+    fn levels_are_read_back_from_robot_names() {
+        assert_eq!(difficulty_from_name("Robot 2 (easy)"), Some(Difficulty::Easy));
+        assert_eq!(difficulty_from_name("Robot 3 (my_bot.py)"), None);
+        assert_eq!(difficulty_from_name("Alice"), None);
+    }
+
+    #[test]
+    fn distances_wrap_through_the_border() {
+        let config = GameConfig::custom_default();
+        let a = GameState_Coord::new_custom(1, 5);
+        assert_eq!(wrapped_distance(&a, &GameState_Coord::new_custom(18, 5), &config), 1);
+        assert_eq!(wrapped_distance(&a, &GameState_Coord::new_custom(3, 18), &config), 7);
+    }
+
+    #[test]
+    fn pocket_is_measured() {
+        let config = GameConfig::custom_default();
+        let occupied = occupied_cells(&pocket_game(), &config);
+        assert_eq!(reachable_area(&GameState_Coord::new_custom(5, 9), &occupied, 20, 20), 2);
+        assert!(reachable_area(&GameState_Coord::new_custom(5, 11), &occupied, 20, 20) > 4);
+    }
+
+    #[test]
+    fn flood_fill_keeps_out_of_pockets_smaller_than_the_snake() {
+        let config = GameConfig::custom_default();
+        let state = pocket_game();
+        // the greedy robot goes for the food and gets stuck
+        assert_eq!(Greedy.choose_direction(&state, &config, 1), Some(Direction::UP));
+        let dir = FloodFill.choose_direction(&state, &config, 1);
+        assert!(matches!(dir, Some(Direction::DOWN) | Some(Direction::RIGHT)), "went {:?}", dir);
+    }
+
+    #[test]
+    fn robots_move_through_the_border() {
+        let config = GameConfig::custom_default();
+        let state = game(vec![snake(1, &[(18, 5), (-1, 0)], Direction::RIGHT)], &[(1, 5)]);
+        assert_eq!(Greedy.choose_direction(&state, &config, 1), Some(Direction::RIGHT));
+        assert_eq!(FloodFill.choose_direction(&state, &config, 1), Some(Direction::RIGHT));
+    }
+
+    #[test]
+    fn random_safe_never_crashes_right_away() {
+        let config = GameConfig::custom_default();
+        // walls above and to the right, only down is free
+        let state = game(vec![
+            snake(1, &[(5, 10), (-3, 0)], Direction::RIGHT),
+            snake(2, &[(4, 9), (2, 0), (0, 2)], Direction::LEFT),
+        ], &[]);
+        for _ in 0..20 {
+            assert_eq!(RandomSafe.choose_direction(&state, &config, 1), Some(Direction::DOWN));
+        }
+    }
+
+    #[test]
+    fn robots_without_a_snake_keep_going() {
+        let config = GameConfig::custom_default();
+        let state = pocket_game();
+        assert_eq!(FloodFill.choose_direction(&state, &config, 7), None);
+    }
+}
@@ -0,0 +1,177 @@
+use piston_window::types::Color;
+
+use crate::BACK_COLOR;
+use crate::drawing::Renderer;
+use crate::dto::{GameConfig, GameState};
+use crate::game_state::BORDER_COLOR;
+use crate::interpolation::Interpolator;
+
+// cells around the head shown in camera mode, border excluded
+const VIEW_SIZE: i32 = 21;
+const VIEW_MARK_COLOR: Color = [1.0, 1.0, 1.0, 0.3];
+
+// Shows only the cells around the local snake's head instead of the whole field
+#[derive(Default)]
+pub struct Camera {
+    enabled: bool,
+    center: (f64, f64),
+}
+
+impl Camera {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // Keeps the last position while the snake is dead
+    pub fn follow(&mut self, interpolator: &Interpolator, state: &GameState, player_id: i32, config: &GameConfig) {
+        if let Some(head) = interpolator.head(state, player_id, config) {
+            self.center = head;
+        }
+    }
+
+    // The board as it is laid out in the window, the view and its frame in camera mode
+    pub fn layout(&self, config: &GameConfig) -> GameConfig {
+        let mut layout = config.clone();
+        if self.enabled {
+            layout.set_width(view_size(config.get_width()) + 2);
+            layout.set_height(view_size(config.get_height()) + 2);
+        }
+        layout
+    }
+
+    pub fn draw(&self, state: &GameState, interpolator: &Interpolator, r: &mut impl Renderer, config: &GameConfig) {
+        if !self.enabled {
+            interpolator.draw(state, r, config);
+            return;
+        }
+
+        let layout = self.layout(config);
+        let (view_width, view_height) = (layout.get_width() - 2, layout.get_height() - 2);
+        let mut viewport = ViewportRenderer {
+            inner: &mut *r,
+            // the head cell stays in the middle of the view
+            origin: (self.center.0 + 0.5 - view_width as f64 / 2.0, self.center.1 + 0.5 - view_height as f64 / 2.0),
+            view: (view_width as f64, view_height as f64),
+            field: (config.get_width(), config.get_height()),
+        };
+        interpolator.draw(state, &mut viewport, config);
+
+        // the frame also hides parts of the cells sticking out of the view
+        let (width, height) = (layout.get_width() as f64, layout.get_height() as f64);
+        r.fill_rect(BORDER_COLOR, 0.0, 0.0, width, 1.0);
+        r.fill_rect(BORDER_COLOR, 0.0, height - 1.0, width, 1.0);
+        r.fill_rect(BORDER_COLOR, 0.0, 0.0, 1.0, height);
+        r.fill_rect(BORDER_COLOR, width - 1.0, 0.0, 1.0, height);
+    }
+
+    // Space the mini-map takes at the bottom of the side panel
+    pub fn panel_reserve(&self, config: &GameConfig) -> f64 {
+        if self.enabled {
+            minimap_scale(config, &self.layout(config)) * config.get_height() as f64 + 1.0
+        } else {
+            0.0
+        }
+    }
+
+    // The whole field in the corner of the side panel with the visible part marked
+    pub fn draw_minimap(&self, r: &mut impl Renderer, state: &GameState, config: &GameConfig) {
+        if !self.enabled {
+            return;
+        }
+
+        let layout = self.layout(config);
+        let scale = minimap_scale(config, &layout);
+        let x = layout.get_width() as f64 + 0.5;
+        let y = layout.get_height() as f64 - scale * config.get_height() as f64 - 0.5;
+
+        r.fill_rect(BACK_COLOR, x, y, scale * config.get_width() as f64, scale * config.get_height() as f64);
+        state.draw(&mut ScaledRenderer { inner: &mut *r, x, y, scale }, config);
+
+        let (view_width, view_height) = ((layout.get_width() - 2) as f64, (layout.get_height() - 2) as f64);
+        let (inner_width, inner_height) = ((config.get_width() - 2) as f64, (config.get_height() - 2) as f64);
+        let left = self.center.0 + 0.5 - view_width / 2.0 - 1.0;
+        let top = self.center.1 + 0.5 - view_height / 2.0 - 1.0;
+        for (span_x, span_width) in wrapped_spans(left, view_width, inner_width) {
+            for (span_y, span_height) in wrapped_spans(top, view_height, inner_height) {
+                r.fill_rect(VIEW_MARK_COLOR, x + (span_x + 1.0) * scale, y + (span_y + 1.0) * scale, span_width * scale, span_height * scale);
+            }
+        }
+    }
+}
+
+fn view_size(field_size: i32) -> i32 {
+    VIEW_SIZE.min(field_size - 2)
+}
+
+fn minimap_scale(config: &GameConfig, layout: &GameConfig) -> f64 {
+    (layout.get_width() as f64 / 2.0 - 1.0) / config.get_width() as f64
+}
+
+// Parts of a segment of the torus with the given period, split where it wraps
+fn wrapped_spans(start: f64, length: f64, period: f64) -> Vec<(f64, f64)> {
+    let start = start.rem_euclid(period);
+    if start + length <= period {
+        vec![(start, length)]
+    } else {
+        vec![(start, period - start), (0.0, start + length - period)]
+    }
+}
+
+// Moves the field under the view, going around through the border like the snakes do
+struct ViewportRenderer<'r, R: Renderer> {
+    inner: &'r mut R,
+    origin: (f64, f64),
+    view: (f64, f64),
+    field: (i32, i32),
+}
+
+impl<R: Renderer> ViewportRenderer<'_, R> {
+    // Position inside the view of a field coordinate, cells touching the view from the left or top included
+    fn to_view(position: f64, origin: f64, field_size: i32) -> f64 {
+        let period = (field_size - 2) as f64;
+        let offset = (position - origin).rem_euclid(period);
+        if offset > period - 1.0 { offset - period } else { offset }
+    }
+}
+
+impl<R: Renderer> Renderer for ViewportRenderer<'_, R> {
+    fn fill_cell(&mut self, color: Color, x: i32, y: i32) {
+        self.fill_rect(color, x as f64, y as f64, 1.0, 1.0);
+    }
+
+    fn fill_rect(&mut self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        let (field_width, field_height) = (self.field.0 as f64, self.field.1 as f64);
+        // the border is not part of the wrapped field, the view gets its own frame
+        if x + width <= 1.0 || x >= field_width - 1.0 || y + height <= 1.0 || y >= field_height - 1.0 {
+            return;
+        }
+
+        let view_x = Self::to_view(x, self.origin.0, self.field.0);
+        let view_y = Self::to_view(y, self.origin.1, self.field.1);
+        if view_x < self.view.0 && view_y < self.view.1 {
+            self.inner.fill_rect(color, view_x + 1.0, view_y + 1.0, width, height);
+        }
+    }
+
+    fn draw_text(&mut self, _color: Color, _size: u32, _text: &str, _x: f64, _y: f64) {}
+}
+
+// Draws a field shrunk by `scale` with its top left corner at (x, y)
+struct ScaledRenderer<'r, R: Renderer> {
+    inner: &'r mut R,
+    x: f64,
+    y: f64,
+    scale: f64,
+}
+
+impl<R: Renderer> Renderer for ScaledRenderer<'_, R> {
+    fn fill_cell(&mut self, color: Color, x: i32, y: i32) {
+        self.fill_rect(color, x as f64, y as f64, 1.0, 1.0);
+    }
+
+    fn fill_rect(&mut self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        self.inner.fill_rect(color, self.x + x * self.scale, self.y + y * self.scale, width * self.scale, height * self.scale);
+    }
+
+    fn draw_text(&mut self, _color: Color, _size: u32, _text: &str, _x: f64, _y: f64) {}
+}
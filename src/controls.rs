@@ -0,0 +1,175 @@
+extern crate serde;
+
+use std::collections::HashMap;
+use piston_window::Key;
+use serde::Deserialize;
+
+use crate::bots::Difficulty;
+use crate::dto::Direction;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Leave,
+    Screenshot,
+    HighScores,
+    AddEasyRobot,
+    AddMediumRobot,
+    AddHardRobot,
+    RemoveRobot,
+    Spectate,
+    Rejoin,
+    ScrollPanelUp,
+    ScrollPanelDown,
+    ToggleCamera,
+}
+
+impl Action {
+    pub fn direction(&self) -> Option<Direction> {
+        match self {
+            Action::Up => Some(Direction::UP),
+            Action::Down => Some(Direction::DOWN),
+            Action::Left => Some(Direction::LEFT),
+            Action::Right => Some(Direction::RIGHT),
+            _ => None,
+        }
+    }
+
+    pub fn robot_difficulty(&self) -> Option<Difficulty> {
+        match self {
+            Action::AddEasyRobot => Some(Difficulty::Easy),
+            Action::AddMediumRobot => Some(Difficulty::Medium),
+            Action::AddHardRobot => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Default,
+    Arrows,
+    Ijkl,
+    Vim,
+    Numpad,
+}
+
+impl Layout {
+    fn steering_keys(&self) -> [(Key, Action); 4] {
+        match self {
+            Layout::Default => [(Key::W, Action::Up), (Key::S, Action::Down), (Key::A, Action::Left), (Key::D, Action::Right)],
+            Layout::Arrows => [(Key::Up, Action::Up), (Key::Down, Action::Down), (Key::Left, Action::Left), (Key::Right, Action::Right)],
+            Layout::Ijkl => [(Key::I, Action::Up), (Key::K, Action::Down), (Key::J, Action::Left), (Key::L, Action::Right)],
+            Layout::Vim => [(Key::K, Action::Up), (Key::J, Action::Down), (Key::H, Action::Left), (Key::L, Action::Right)],
+            Layout::Numpad => [(Key::NumPad8, Action::Up), (Key::NumPad5, Action::Down), (Key::NumPad4, Action::Left), (Key::NumPad6, Action::Right)],
+        }
+    }
+}
+
+fn default_hotseat_layouts() -> Vec<Layout> {
+    vec![Layout::Arrows, Layout::Default, Layout::Ijkl, Layout::Numpad]
+}
+
+// [controls] section of the settings file, e.g.
+//   layout = "vim"
+//   [controls.bindings]
+//   pause = ["Space"]
+// Keys listed for an action replace the layout's keys for that action.
+// In hot-seat games every local player steers with the layout of its seat from `hotseat`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ControlsSettings {
+    pub layout: Layout,
+    pub bindings: HashMap<Action, Vec<Key>>,
+    pub hotseat: Vec<Layout>,
+}
+
+impl Default for ControlsSettings {
+    fn default() -> Self {
+        ControlsSettings {
+            layout: Layout::default(),
+            bindings: HashMap::new(),
+            hotseat: default_hotseat_layouts(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    keys: HashMap<Key, Action>,
+}
+
+impl KeyBindings {
+    pub fn from_settings(settings: &ControlsSettings) -> KeyBindings {
+        let mut steering = Layout::Arrows.steering_keys().to_vec();
+        steering.extend(settings.layout.steering_keys());
+        KeyBindings::with_steering(settings, steering)
+    }
+
+    // Key sets for everyone playing in this window, the first one belongs to the host
+    pub fn for_local_players(settings: &ControlsSettings, players: usize) -> Vec<KeyBindings> {
+        if players == 1 {
+            vec![KeyBindings::from_settings(settings)]
+        } else {
+            KeyBindings::hotseat(settings, players)
+        }
+    }
+
+    // One key set per local player, the first one also gets the game actions.
+    // The number of seats is checked against `hotseat` while parsing the arguments.
+    fn hotseat(settings: &ControlsSettings, seats: usize) -> Vec<KeyBindings> {
+        settings.hotseat.iter().take(seats).enumerate().map(|(seat, layout)| {
+            if seat == 0 {
+                KeyBindings::with_steering(settings, layout.steering_keys().to_vec())
+            } else {
+                KeyBindings { keys: layout.steering_keys().into_iter().collect() }
+            }
+        }).collect()
+    }
+
+    fn with_steering(settings: &ControlsSettings, steering: Vec<(Key, Action)>) -> KeyBindings {
+        let mut defaults = vec![
+            (Key::P, Action::Pause),
+            (Key::Escape, Action::Leave),
+            (Key::F12, Action::Screenshot),
+            (Key::Tab, Action::HighScores),
+            (Key::D1, Action::AddEasyRobot),
+            (Key::D2, Action::AddMediumRobot),
+            (Key::D3, Action::AddHardRobot),
+            (Key::Equals, Action::AddHardRobot),
+            (Key::Minus, Action::RemoveRobot),
+            (Key::Return, Action::Spectate),
+            (Key::R, Action::Rejoin),
+            (Key::PageUp, Action::ScrollPanelUp),
+            (Key::PageDown, Action::ScrollPanelDown),
+            (Key::C, Action::ToggleCamera),
+        ];
+        defaults.extend(steering);
+
+        let mut keys: HashMap<Key, Action> = defaults.into_iter()
+            .filter(|(_, action)| !settings.bindings.contains_key(action))
+            .collect();
+        for (action, bound) in &settings.bindings {
+            for key in bound {
+                keys.insert(*key, *action);
+            }
+        }
+        KeyBindings { keys }
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.keys.get(&key).cloned()
+    }
+
+    // The key shown to the player for an action
+    pub fn key_for(&self, action: Action) -> Option<Key> {
+        self.keys.iter().filter(|(_, a)| **a == action).map(|(key, _)| *key).min()
+    }
+}
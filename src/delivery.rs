@@ -0,0 +1,232 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::sync::oneshot;
+
+use crate::dto::{GameConfig, GamePlayer};
+
+// enough to outlast the retransmissions of a message even while a peer sends a lot
+const REMEMBERED_PER_PEER: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Acked,
+    // the peer was declared dead before it acked the message
+    Failed,
+    // messages without a receiver (like announcements) are sent once and never acked
+    Sent,
+}
+
+// Senders waiting for the result of every unacked message, by peer and msg_seq
+type InFlight = HashMap<i32, HashMap<i64, oneshot::Sender<Delivery>>>;
+
+// Messages sent to every peer that wait for an AckMsg with their msg_seq
+#[derive(Clone)]
+pub struct Deliveries {
+    in_flight: Arc<Mutex<InFlight>>,
+    last_sent: Arc<Mutex<HashMap<i32, Instant>>>,
+    // peers declared dead get every message once without resending it, until they are heard from again
+    dead: Arc<Mutex<HashSet<i32>>>,
+    resend_interval: Duration,
+}
+
+impl Deliveries {
+    pub fn new(config: &GameConfig) -> Self {
+        Deliveries {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            last_sent: Arc::new(Mutex::new(HashMap::new())),
+            dead: Arc::new(Mutex::new(HashSet::new())),
+            // a zero interval would make tokio panic on very fast games
+            resend_interval: Duration::from_millis((config.get_state_delay_ms() as u64 / 10).max(1)),
+        }
+    }
+
+    pub fn resend_interval(&self) -> Duration {
+        self.resend_interval
+    }
+
+    pub async fn sent(&self, peer: i32) {
+        self.last_sent.lock().await.insert(peer, Instant::now());
+    }
+
+    pub async fn idle_for(&self, peer: i32) -> Duration {
+        self.last_sent.lock().await.get(&peer).map_or(Duration::MAX, |since| since.elapsed())
+    }
+
+    // The receiver learns whether the peer acked the message
+    pub async fn track(&self, peer: i32, msg_seq: i64) -> oneshot::Receiver<Delivery> {
+        let (sender, receiver) = oneshot::channel();
+        self.in_flight.lock().await.entry(peer).or_default().insert(msg_seq, sender);
+        receiver
+    }
+
+    pub async fn is_in_flight(&self, peer: i32, msg_seq: i64) -> bool {
+        self.in_flight.lock().await.get(&peer).is_some_and(|messages| messages.contains_key(&msg_seq))
+    }
+
+    pub async fn ack(&self, peer: i32, msg_seq: i64) {
+        if let Some(sender) = self.in_flight.lock().await.get_mut(&peer).and_then(|messages| messages.remove(&msg_seq)) {
+            let _ = sender.send(Delivery::Acked);
+        }
+    }
+
+    // Gives up on everything still waiting for the peer, later messages are not resent until it is heard from
+    pub async fn declare_dead(&self, peer: i32) {
+        self.dead.lock().await.insert(peer);
+        for (_, sender) in self.in_flight.lock().await.remove(&peer).unwrap_or_default() {
+            let _ = sender.send(Delivery::Failed);
+        }
+    }
+
+    pub async fn is_dead(&self, peer: i32) -> bool {
+        self.dead.lock().await.contains(&peer)
+    }
+
+    pub async fn heard_from(&self, peer: i32) {
+        self.dead.lock().await.remove(&peer);
+    }
+}
+
+// When every peer was heard from last, a peer silent for 0.8 of a turn is dead
+#[derive(Clone)]
+pub struct Liveness {
+    last_seen: Arc<Mutex<HashMap<i32, Instant>>>,
+    // peers that said they leave, until the next turn takes them out
+    left: Arc<Mutex<HashSet<i32>>>,
+    timeout: Duration,
+}
+
+impl Liveness {
+    // everybody already in the game counts as just heard from
+    pub fn new(config: &GameConfig, players: &[GamePlayer]) -> Self {
+        Liveness {
+            last_seen: Arc::new(Mutex::new(players.iter().map(|p| (p.get_id(), Instant::now())).collect())),
+            left: Arc::new(Mutex::new(HashSet::new())),
+            timeout: Duration::from_millis(config.get_state_delay_ms() as u64 * 8 / 10),
+        }
+    }
+
+    pub async fn saw(&self, peer: i32) {
+        self.last_seen.lock().await.insert(peer, Instant::now());
+    }
+
+    pub async fn is_alive(&self, peer: i32) -> bool {
+        self.last_seen.lock().await.get(&peer).is_some_and(|since| since.elapsed() < self.timeout)
+    }
+
+    pub async fn left(&self, peer: i32) {
+        self.left.lock().await.insert(peer);
+    }
+
+    pub async fn take_left(&self) -> Vec<i32> {
+        self.left.lock().await.drain().collect()
+    }
+}
+
+// msg_seq of the latest messages from every peer, so retransmitted ones are acked but applied only once
+#[derive(Default)]
+pub struct SeenMessages {
+    seen: HashMap<SocketAddr, (HashSet<i64>, VecDeque<i64>)>,
+}
+
+impl SeenMessages {
+    pub fn first_time(&mut self, peer: SocketAddr, msg_seq: i64) -> bool {
+        let (seen, order) = self.seen.entry(peer).or_default();
+        if !seen.insert(msg_seq) {
+            return false;
+        }
+        order.push_back(msg_seq);
+        if order.len() > REMEMBERED_PER_PEER {
+            seen.remove(&order.pop_front().unwrap());
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn retransmissions_are_seen_once_per_peer() {
+        let mut seen = SeenMessages::default();
+        assert!(seen.first_time(peer(1000), 7));
+        assert!(!seen.first_time(peer(1000), 7));
+        assert!(seen.first_time(peer(1001), 7));
+        assert!(seen.first_time(peer(1000), 8));
+    }
+
+    #[test]
+    fn old_messages_are_forgotten() {
+        let mut seen = SeenMessages::default();
+        seen.first_time(peer(1000), 0);
+        for msg_seq in 1..REMEMBERED_PER_PEER as i64 {
+            seen.first_time(peer(1000), msg_seq);
+        }
+        assert!(!seen.first_time(peer(1000), 0));
+
+        seen.first_time(peer(1000), REMEMBERED_PER_PEER as i64);
+        assert!(seen.first_time(peer(1000), 0));
+    }
+
+    #[tokio::test]
+    async fn acked_and_failed_deliveries_are_reported() {
+        let deliveries = Deliveries::new(&GameConfig::custom_default());
+        let acked = deliveries.track(2, 10).await;
+        let failed = deliveries.track(2, 11).await;
+        let other_peer = deliveries.track(3, 10).await;
+
+        deliveries.ack(2, 10).await;
+        assert_eq!(acked.await, Ok(Delivery::Acked));
+        assert!(!deliveries.is_in_flight(2, 10).await);
+        assert!(deliveries.is_in_flight(2, 11).await);
+
+        deliveries.declare_dead(2).await;
+        assert_eq!(failed.await, Ok(Delivery::Failed));
+        assert!(deliveries.is_in_flight(3, 10).await);
+
+        deliveries.ack(3, 10).await;
+        assert_eq!(other_peer.await, Ok(Delivery::Acked));
+    }
+
+    #[tokio::test]
+    async fn dead_peers_are_resent_to_after_they_are_heard_from() {
+        let deliveries = Deliveries::new(&GameConfig::custom_default());
+        assert!(!deliveries.is_dead(2).await);
+        deliveries.declare_dead(2).await;
+        assert!(deliveries.is_dead(2).await);
+        assert!(!deliveries.is_dead(3).await);
+
+        deliveries.heard_from(2).await;
+        assert!(!deliveries.is_dead(2).await);
+    }
+
+    #[test]
+    fn resend_interval_is_never_zero() {
+        let mut config = GameConfig::custom_default();
+        assert_eq!(Deliveries::new(&config).resend_interval(), Duration::from_millis(30));
+        for state_delay_ms in [0, 1, 9] {
+            config.set_state_delay_ms(state_delay_ms);
+            assert!(Deliveries::new(&config).resend_interval() >= Duration::from_millis(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn players_in_the_game_start_alive() {
+        let mut player = GamePlayer::default();
+        player.set_id(2);
+        let liveness = Liveness::new(&GameConfig::custom_default(), &[player]);
+        assert!(liveness.is_alive(2).await);
+        assert!(!liveness.is_alive(3).await);
+
+        liveness.left(2).await;
+        assert_eq!(liveness.take_left().await, vec![2]);
+        assert!(liveness.take_left().await.is_empty());
+    }
+}
@@ -1,27 +1,287 @@
-
-use piston_window::Context;
-use piston_window::G2d;
+use std::path::Path;
+use piston_window::{text, Context, G2d, Glyphs, PistonWindow, TextureSettings, Transformed};
 use piston_window::rectangle;
 use piston_window::types::Color;
 
-const BLOCK_SIZE: f64 = 25.0;
+use crate::dto::{GameConfig, GamePlayer, GameState, GameState_Snake_SnakeState, NodeRole, PlayerType};
+
+// preferred cell size in pixels, the window shrinks it to fit large fields on the screen
+pub const BLOCK_SIZE: f64 = 25.0;
+const MAX_WINDOW_SIZE: [f64; 2] = [1280.0, 800.0];
+const BUNDLED_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+pub fn to_gui_coord_f64(game_coord: i32, cell_size: f64) -> f64 {
+    (game_coord as f64) * cell_size
+}
+
+// Largest cell size showing the whole field and the side panel in a window of the given size
+pub fn cell_size_for(config: &GameConfig, window_size: [f64; 2]) -> f64 {
+    let [width, height] = window_size;
+    (width / (config.get_width() as f64 * 1.5)).min(height / config.get_height() as f64)
+}
+
+pub fn initial_window_size(config: &GameConfig) -> [f64; 2] {
+    let cell_size = BLOCK_SIZE.min(cell_size_for(config, MAX_WINDOW_SIZE));
+    [to_gui_coord_f64(config.get_width(), cell_size) * 1.5, to_gui_coord_f64(config.get_height(), cell_size)]
+}
+
+// Everything the game draws, positions and sizes are measured in cells of the field
+pub trait Renderer {
+    fn fill_cell(&mut self, color: Color, x: i32, y: i32);
+
+    fn fill_rect(&mut self, color: Color, x: f64, y: f64, width: f64, height: f64);
+
+    // y is the baseline of the text, size is in pixels at the default cell size
+    fn draw_text(&mut self, color: Color, size: u32, text: &str, x: f64, y: f64);
+}
+
+pub struct PistonRenderer<'a, 'b> {
+    context: &'a Context,
+    graphics: &'a mut G2d<'b>,
+    // without a font the board is still drawn, only the text is left out
+    glyphs: Option<&'a mut Glyphs>,
+    cell_size: f64,
+}
+
+impl<'a, 'b> PistonRenderer<'a, 'b> {
+    pub fn new(context: &'a Context, graphics: &'a mut G2d<'b>, glyphs: Option<&'a mut Glyphs>, cell_size: f64) -> Self {
+        PistonRenderer { context, graphics, glyphs, cell_size }
+    }
+}
+
+impl Renderer for PistonRenderer<'_, '_> {
+    fn fill_cell(&mut self, color: Color, x: i32, y: i32) {
+        rectangle(color, [to_gui_coord_f64(x, self.cell_size), to_gui_coord_f64(y, self.cell_size),
+            self.cell_size, self.cell_size], self.context.transform, self.graphics);
+    }
+
+    fn fill_rect(&mut self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        let cell_size = self.cell_size;
+        rectangle(color, [x * cell_size, y * cell_size,
+            width * cell_size, height * cell_size], self.context.transform, self.graphics);
+    }
+
+    fn draw_text(&mut self, color: Color, size: u32, text_line: &str, x: f64, y: f64) {
+        let Some(glyphs) = self.glyphs.as_deref_mut() else {
+            return;
+        };
+        let size = ((size as f64 * self.cell_size / BLOCK_SIZE).round() as u32).max(1);
+        text(
+            color,
+            size,
+            text_line,
+            glyphs,
+            self.context.transform.trans(x * self.cell_size, y * self.cell_size),
+            self.graphics,
+        ).expect("failed to draw text");
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCall {
+    Cell { color: Color, x: i32, y: i32 },
+    Rect { color: Color, x: f64, y: f64, width: f64, height: f64 },
+    Text { color: Color, size: u32, text: String, x: f64, y: f64 },
+}
+
+// Keeps the draw calls in memory, for backends that rasterize them later and for checking what was drawn
+#[derive(Default)]
+pub struct RecordingRenderer {
+    pub calls: Vec<DrawCall>,
+}
+
+impl Renderer for RecordingRenderer {
+    fn fill_cell(&mut self, color: Color, x: i32, y: i32) {
+        self.calls.push(DrawCall::Cell { color, x, y });
+    }
+
+    fn fill_rect(&mut self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        self.calls.push(DrawCall::Rect { color, x, y, width, height });
+    }
 
-pub fn to_gui_coord_f64(game_coord: i32) -> f64 {
-    (game_coord as f64) * BLOCK_SIZE
+    fn draw_text(&mut self, color: Color, size: u32, text: &str, x: f64, y: f64) {
+        self.calls.push(DrawCall::Text { color, size, text: text.to_string(), x, y });
+    }
 }
 
-pub fn draw_block(color: Color, x: i32, y: i32, con: &Context, g: &mut G2d) {
-    let gui_x = to_gui_coord_f64(x);
-    let gui_y = to_gui_coord_f64(y);
+// Prefers the font from the settings and falls back to the one built into the binary
+pub fn load_glyphs(window: &mut PistonWindow, font: Option<&Path>) -> Option<Glyphs> {
+    if let Some(path) = font {
+        match window.load_font(path) {
+            Ok(glyphs) => return Some(glyphs),
+            Err(e) => eprintln!("failed to load font {}, using the bundled one: {}", path.display(), e),
+        }
+    }
 
-    rectangle(color, [gui_x, gui_y,
-        BLOCK_SIZE, BLOCK_SIZE], con.transform, g);
+    match Glyphs::from_bytes(BUNDLED_FONT, window.create_texture_context(), TextureSettings::new()) {
+        Ok(glyphs) => Some(glyphs),
+        Err(_) => {
+            eprintln!("failed to load the bundled font, text will not be shown");
+            None
+        }
+    }
 }
 
-pub fn draw_rectangle(color: Color, start_x: i32, start_y: i32, width: i32, height: i32, con: &Context, g: &mut G2d) {
-    let gui_start_x = to_gui_coord_f64(start_x);
-    let gui_start_y = to_gui_coord_f64(start_y);
+const PANEL_TOP: f64 = 1.6;
+const PANEL_ROW_HEIGHT: f64 = 1.8;
+const PANEL_NAME_LENGTH: usize = 14;
+
+// How many players fit into the side panel at once, `reserved` cells at the bottom are kept free
+fn side_panel_rows(config: &GameConfig, reserved: f64) -> usize {
+    ((config.get_height() as f64 - reserved - PANEL_TOP - PANEL_ROW_HEIGHT) / PANEL_ROW_HEIGHT).max(1.0) as usize
+}
+
+// How far the side panel can be scrolled
+pub fn side_panel_overflow(config: &GameConfig, state: &GameState, reserved: f64) -> usize {
+    state.get_players().get_players().len().saturating_sub(side_panel_rows(config, reserved))
+}
+
+// Leaderboard sorted by score, `scroll` skips the first rows when not everybody fits
+pub fn draw_side_panel(r: &mut impl Renderer, config: &GameConfig, state: &GameState, my_id: i32, scroll: usize, reserved: f64) {
+    // Define the side panel dimensions
+    let side_panel_x = config.get_width() as f64;
+    let side_panel_width = config.get_width() as f64 / 2.0;
+    let side_panel_height = config.get_height() as f64;
+
+    // Set the color for the side panel background
+    let side_panel_color = [0.9, 0.9, 0.9, 1.0];
+
+    // Draw the side panel background
+    r.fill_rect(side_panel_color, side_panel_x, 0.0, side_panel_width, side_panel_height);
+
+    // Set the color for the text
+    let text_color = [0.0, 0.0, 0.0, 1.0];
+    let details_color = [0.3, 0.3, 0.3, 1.0];
+    let highlight_color = [1.0, 0.93, 0.6, 1.0];
+
+    let mut players: Vec<&GamePlayer> = state.get_players().get_players().iter().collect();
+    players.sort_by(|a, b| b.get_score().cmp(&a.get_score()).then_with(|| a.get_name().cmp(b.get_name())));
+
+    let rows = side_panel_rows(config, reserved);
+    let scroll = scroll.min(side_panel_overflow(config, state, reserved));
+
+    let mut y_position = PANEL_TOP;
+    if scroll > 0 {
+        r.draw_text(details_color, 12, &format!("{} more above", scroll), side_panel_x + 1.6, y_position - 0.6);
+    }
+    for (index, player) in players.iter().enumerate().skip(scroll).take(rows) {
+        let snake = state.get_snakes().iter().find(|s| s.get_player_id() == player.get_id());
+
+        if player.get_id() == my_id {
+            r.fill_rect(highlight_color, side_panel_x + 0.2, y_position - 0.8, side_panel_width - 0.4, PANEL_ROW_HEIGHT - 0.1);
+        }
+        r.fill_rect(state.player_color(player.get_id()), side_panel_x + 0.5, y_position - 0.6, 0.7, 0.7);
+
+        let mut name: String = player.get_name().chars().take(PANEL_NAME_LENGTH).collect();
+        if name.chars().count() < player.get_name().chars().count() {
+            name.push('…');
+        }
+        let badge = match player.get_role() {
+            NodeRole::MASTER => " [master]",
+            NodeRole::DEPUTY => " [deputy]",
+            _ => "",
+        };
+        let robot = if player.get_field_type() == PlayerType::ROBOT { " [bot]" } else { "" };
+        r.draw_text(text_color, 15, &format!("{}. {}{}{}", index + 1, name, badge, robot), side_panel_x + 1.6, y_position);
+
+        let status = match snake.map(|s| s.get_state()) {
+            Some(GameState_Snake_SnakeState::ALIVE) => "alive",
+            Some(GameState_Snake_SnakeState::ZOMBIE) => "zombie",
+            None => "dead",
+        };
+        r.draw_text(
+            details_color,
+            12,
+            &format!("score {}, length {}, {}", player.get_score(), snake.map_or(0, |s| s.get_length()), status),
+            side_panel_x + 1.6,
+            y_position + 0.7,
+        );
+        y_position += PANEL_ROW_HEIGHT; // Adjust the spacing between player information
+    }
+
+    let below = players.len().saturating_sub(scroll + rows);
+    if below > 0 {
+        r.draw_text(details_color, 12, &format!("{} more below", below), side_panel_x + 1.6, y_position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::{GameState_Coord, NodeRole::NORMAL};
+    use crate::game_state::{BORDER_COLOR, FOOD_COLOR};
+
+    // Alice hosts with her snake at (2, 2) heading right, Bob has a better score but no snake
+    fn small_game() -> (GameConfig, GameState) {
+        let config = GameConfig::custom_default();
+        let mut state = GameState::new_custom("Alice".into(), "127.0.0.1".into(), 1);
+        state.mut_foods().clear();
+        state.mut_foods().push(GameState_Coord::new_custom(5, 6));
+        let mut bob = GamePlayer::new_with_ip("Bob".into(), 2, NORMAL, "127.0.0.1".into(), 2);
+        bob.set_score(4);
+        state.mut_players().mut_players().push(bob);
+        (config, state)
+    }
+
+    // Colours of the cell and rect calls that cover the middle of a cell
+    fn colors_at(calls: &[DrawCall], cell_x: i32, cell_y: i32) -> Vec<Color> {
+        let (center_x, center_y) = (cell_x as f64 + 0.5, cell_y as f64 + 0.5);
+        calls.iter().filter_map(|call| match call {
+            DrawCall::Cell { color, x, y } if *x == cell_x && *y == cell_y => Some(*color),
+            DrawCall::Rect { color, x, y, width, height }
+                if (*x..x + width).contains(&center_x) && (*y..y + height).contains(&center_y) => Some(*color),
+            _ => None,
+        }).collect()
+    }
+
+    fn texts(calls: &[DrawCall]) -> Vec<&str> {
+        calls.iter().filter_map(|call| match call {
+            DrawCall::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn draws_border_food_and_snake() {
+        let (config, state) = small_game();
+        let mut r = RecordingRenderer::default();
+        state.draw(&mut r, &config);
+
+        for (x, y, width, height) in [(0.0, 0.0, 20.0, 1.0), (0.0, 19.0, 20.0, 1.0), (0.0, 0.0, 1.0, 20.0), (19.0, 0.0, 1.0, 20.0)] {
+            assert!(r.calls.contains(&DrawCall::Rect { color: BORDER_COLOR, x, y, width, height }), "no border at {} {}", x, y);
+        }
+        assert!(r.calls.contains(&DrawCall::Cell { color: FOOD_COLOR, x: 5, y: 6 }));
+
+        let color = state.player_color(1);
+        assert!(colors_at(&r.calls, 2, 2).contains(&color), "head not drawn");
+        // the tail is a darker shade of the snake's colour
+        let tail = colors_at(&r.calls, 1, 2);
+        assert!(tail.iter().any(|c| c != &color && (0..3).all(|i| c[i] <= color[i])), "tail not drawn");
+        assert!(!colors_at(&r.calls, 3, 2).contains(&color), "snake drawn ahead of its head");
+    }
+
+    #[test]
+    fn side_panel_ranks_players_by_score() {
+        let (config, state) = small_game();
+        let mut r = RecordingRenderer::default();
+        draw_side_panel(&mut r, &config, &state, 1, 0, 0.0);
+
+        assert_eq!(texts(&r.calls), ["1. Bob", "score 4, length 0, dead", "2. Alice [master]", "score 0, length 2, alive"]);
+        assert!(r.calls.iter().any(|call| matches!(call, DrawCall::Rect { x, .. } if *x == 20.0)), "no panel background");
+    }
+
+    #[test]
+    fn side_panel_scrolls_when_players_do_not_fit() {
+        let (config, mut state) = small_game();
+        for id in 3..20 {
+            state.mut_players().mut_players().push(GamePlayer::new_with_ip(format!("P{:02}", id), id, NORMAL, "127.0.0.1".into(), id));
+        }
+        let mut r = RecordingRenderer::default();
+        draw_side_panel(&mut r, &config, &state, 1, 1, 0.0);
 
-    rectangle(color, [gui_start_x, gui_start_y,
-        BLOCK_SIZE * (width as f64), BLOCK_SIZE * (height as f64)], con.transform, g);
+        let texts = texts(&r.calls);
+        assert_eq!(texts[0], "1 more above");
+        assert_eq!(texts[1], "2. Alice [master]");
+        assert!(texts.last().unwrap().ends_with("more below"));
+    }
 }
@@ -0,0 +1,158 @@
+use std::cell::Cell;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, sync_channel, Receiver, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::bots::Strategy;
+use crate::dto::{Direction, GameConfig, GameState, GameState_Coord};
+
+// A robot played by another program: every tick it gets the state as one JSON line on stdin
+// and answers with one line like {"direction": "UP"} on stdout.
+// Answers later than the time budget are dropped and the snake keeps its direction.
+pub struct ExternalBot {
+    child: Child,
+    states: SyncSender<String>,
+    answers: Receiver<String>,
+    budget: Duration,
+    asked_at: Cell<Option<(Instant, i32)>>,
+}
+
+#[derive(Deserialize)]
+struct Answer {
+    direction: String,
+    // lets the bot tell which state the answer is for
+    state_order: Option<i32>,
+}
+
+impl ExternalBot {
+    // The command is split at whitespace without any quoting, the first word is the program
+    pub fn spawn(command: &str, budget: Duration) -> Result<ExternalBot, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty external bot command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("failed to start external bot {}: {}", command, e))?;
+
+        let mut stdin = child.stdin.take().expect("external bot stdin is piped");
+        let stdout = child.stdout.take().expect("external bot stdout is piped");
+
+        // a bot that stops reading fills the pipe, only this thread waits for it and not the game
+        let (states, queued) = sync_channel::<String>(1);
+        thread::spawn(move || {
+            for line in queued {
+                if writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let (sender, answers) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        return;
+                    },
+                    Err(_) => return,
+                }
+            }
+        });
+
+        Ok(ExternalBot { child, states, answers, budget, asked_at: Cell::new(None) })
+    }
+}
+
+impl Strategy for ExternalBot {
+    fn prepare(&self, state: &GameState, config: &GameConfig, player_id: i32) {
+        // whatever is still queued answers an older state
+        while self.answers.try_recv().is_ok() {}
+
+        let line = state_to_json(state, config, player_id).to_string();
+        // skipped while the bot has not read the state before or is gone
+        match self.states.try_send(line) {
+            Ok(()) => self.asked_at.set(Some((Instant::now(), state.get_state_order()))),
+            Err(_) => self.asked_at.set(None),
+        }
+    }
+
+    fn choose_direction(&self, _state: &GameState, _config: &GameConfig, _player_id: i32) -> Option<Direction> {
+        let (asked_at, state_order) = self.asked_at.take()?;
+        let deadline = asked_at + self.budget;
+
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = self.answers.recv_timeout(left).ok()?;
+            let answer: Answer = match serde_json::from_str(&line) {
+                Ok(answer) => answer,
+                Err(e) => {
+                    eprintln!("external bot sent an invalid answer {}: {}", line, e);
+                    continue;
+                },
+            };
+            if answer.state_order.is_some_and(|order| order != state_order) {
+                continue;
+            }
+            return parse_direction(&answer.direction);
+        }
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn parse_direction(direction: &str) -> Option<Direction> {
+    match direction.to_uppercase().as_str() {
+        "UP" => Some(Direction::UP),
+        "DOWN" => Some(Direction::DOWN),
+        "LEFT" => Some(Direction::LEFT),
+        "RIGHT" => Some(Direction::RIGHT),
+        _ => {
+            eprintln!("external bot sent an unknown direction {}", direction);
+            None
+        },
+    }
+}
+
+fn coord_to_json(coord: &GameState_Coord) -> Value {
+    json!({ "x": coord.get_x(), "y": coord.get_y() })
+}
+
+pub fn state_to_json(state: &GameState, config: &GameConfig, player_id: i32) -> Value {
+    json!({
+        "you": player_id,
+        "state_order": state.get_state_order(),
+        "config": {
+            "width": config.get_width(),
+            "height": config.get_height(),
+            "food_static": config.get_food_static(),
+            "state_delay_ms": config.get_state_delay_ms(),
+        },
+        "snakes": state.get_snakes().iter().map(|s| json!({
+            "player_id": s.get_player_id(),
+            "state": format!("{:?}", s.get_state()),
+            "head_direction": format!("{:?}", s.get_head_direction()),
+            // as in the protocol: the head, then offsets between key points
+            "points": s.get_points().iter().map(coord_to_json).collect::<Vec<_>>(),
+            "cells": s.get_cells(config.get_width(), config.get_height()).iter().map(coord_to_json).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "foods": state.get_foods().iter().map(coord_to_json).collect::<Vec<_>>(),
+        "players": state.get_players().get_players().iter().map(|p| json!({
+            "id": p.get_id(),
+            "name": p.get_name(),
+            "role": format!("{:?}", p.get_role()),
+            "type": format!("{:?}", p.get_field_type()),
+            "score": p.get_score(),
+        })).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use crate::controls::{Action, KeyBindings};
+use crate::drawing::Renderer;
+use crate::dto::{Direction, GameConfig, GameState};
+use crate::dto::NodeRole::VIEWER;
+use crate::records::MatchTracker;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeathCause {
+    OwnBody,
+    Snake(String),
+    // nothing was in the way, the master removed the snake (e.g. after a timeout)
+    Removed,
+}
+
+impl DeathCause {
+    fn describe(&self) -> String {
+        match self {
+            DeathCause::OwnBody => "You ran into your own tail".to_string(),
+            DeathCause::Snake(name) => format!("You crashed into {}", name),
+            DeathCause::Removed => "You were removed from the game".to_string(),
+        }
+    }
+}
+
+pub struct GameOver {
+    cause: DeathCause,
+    score: i32,
+    rank: usize,
+    players: usize,
+    survived: Duration,
+}
+
+// Watches the states shown to one player and notices when its snake dies
+#[derive(Default)]
+pub struct DeathWatch {
+    last_alive: Option<GameState>,
+    game_over: Option<GameOver>,
+    spectating: bool,
+}
+
+impl DeathWatch {
+    pub fn observe(&mut self, state: &GameState, config: &GameConfig, player_id: i32, tracker: &MatchTracker) {
+        if state.get_snakes().iter().any(|s| s.get_player_id() == player_id) {
+            // alive again after rejoining
+            self.last_alive = Some(state.clone());
+            self.game_over = None;
+            self.spectating = false;
+            return;
+        }
+
+        let Some(player) = state.get_players().get_players().iter().find(|p| p.get_id() == player_id) else {
+            return;
+        };
+        if self.game_over.is_some() || player.get_role() != VIEWER {
+            return;
+        }
+        // players who joined as viewers never had a snake to lose
+        let Some(before) = &self.last_alive else {
+            return;
+        };
+
+        let score = player.get_score();
+        let players = state.get_players().get_players();
+        self.game_over = Some(GameOver {
+            cause: death_cause(before, state, player_id, config),
+            score,
+            rank: 1 + players.iter().filter(|p| p.get_score() > score).count(),
+            players: players.len(),
+            survived: tracker.survived(player_id),
+        });
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over.is_some()
+    }
+
+    pub fn spectate(&mut self) {
+        self.spectating = true;
+    }
+
+    // Covers the given area (in cells) with the summary of the last life, unless the player went on watching
+    pub fn draw(&self, r: &mut impl Renderer, bindings: &KeyBindings, can_rejoin: bool, width: f64, height: f64) {
+        let Some(game_over) = &self.game_over else {
+            return;
+        };
+        if self.spectating {
+            return;
+        }
+
+        r.fill_rect([0.0, 0.0, 0.0, 0.75], 0.0, 0.0, width, height);
+
+        let text_color = [1.0, 1.0, 1.0, 1.0];
+        let key = |action| bindings.key_for(action).map_or("-".to_string(), |k| format!("{:?}", k));
+        let mut options = vec![format!("{} - keep watching", key(Action::Spectate))];
+        if can_rejoin {
+            options.push(format!("{} - rejoin", key(Action::Rejoin)));
+        }
+        options.push(format!("{} - quit", key(Action::Leave)));
+
+        let lines = [
+            game_over.cause.describe(),
+            format!("Score: {}, rank {} of {}", game_over.score, game_over.rank, game_over.players),
+            format!("Survived: {}s", game_over.survived.as_secs()),
+        ];
+
+        r.draw_text(text_color, 30, "Game over", 1.6, height / 3.0);
+        let mut y_position = height / 3.0 + 1.6;
+        for line in lines {
+            r.draw_text(text_color, 15, &line, 1.6, y_position);
+            y_position += 1.0;
+        }
+        r.draw_text(text_color, 15, &options.join(", "), 1.6, y_position + 1.0);
+    }
+}
+
+// Replays the last turn of the snake to find what it ran into
+fn death_cause(before: &GameState, after: &GameState, player_id: i32, config: &GameConfig) -> DeathCause {
+    let (width, height) = (config.get_width(), config.get_height());
+    let Some(snake) = before.get_snakes().iter().find(|s| s.get_player_id() == player_id) else {
+        return DeathCause::Removed;
+    };
+
+    let own_cells = snake.get_cells(width, height);
+    let heading = snake.get_head_direction();
+    // the snake most likely kept its heading, otherwise it turned
+    let directions = [heading, Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT];
+
+    for direction in directions.into_iter().filter(|d| *d != heading.opposite()) {
+        let next = snake.next_head_position(Some(direction), width, height);
+
+        if own_cells[..own_cells.len().saturating_sub(1)].contains(&next) {
+            return DeathCause::OwnBody;
+        }
+        // head-on crashes remove both snakes, so the state before is checked as well
+        let other = after.get_snakes().iter().chain(before.get_snakes())
+            .filter(|s| s.get_player_id() != player_id)
+            .find(|s| s.is_overlap(&next, width, height));
+        if let Some(other) = other {
+            let name = after.get_players().get_players().iter()
+                .find(|p| p.get_id() == other.get_player_id())
+                .map_or("another snake".to_string(), |p| p.get_name().to_string());
+            return DeathCause::Snake(name);
+        }
+    }
+    DeathCause::Removed
+}
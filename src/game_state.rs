@@ -2,16 +2,17 @@ extern crate serde;
 
 use std::collections::HashMap;
 use piston_window::types::Color;
-use piston_window::*;
 
-use crate::drawing::{draw_block, draw_rectangle};
+use crate::drawing::Renderer;
+use crate::palette;
 use rand::{thread_rng, Rng};
-use crate::dto::{Direction, GameAnnouncement, GameConfig, GamePlayer, GamePlayers, GameState, GameState_Coord, GameState_Snake, NodeRole};
-use crate::dto::NodeRole::{MASTER, VIEWER};
-use crate::dto::PlayerType::HUMAN;
+use crate::dto::{Direction, GameAnnouncement, GameConfig, GamePlayer, GamePlayers, GameState, GameState_Coord, GameState_Snake, NodeRole, PlayerType};
+use crate::dto::NodeRole::{MASTER, NORMAL, VIEWER};
+use crate::dto::PlayerType::{HUMAN, ROBOT};
+use crate::dto::GameState_Snake_SnakeState::ZOMBIE;
 
-const FOOD_COLOR: Color = [0.90, 0.49, 0.13, 1.0];
-const BORDER_COLOR: Color = [0.741, 0.765, 0.78, 1.0];
+pub const FOOD_COLOR: Color = [0.90, 0.49, 0.13, 1.0];
+pub const BORDER_COLOR: Color = [0.741, 0.765, 0.78, 1.0];
 
 impl GamePlayer {
     pub(crate) fn new_with_ip(name: String, id: i32, role: NodeRole, ipv4addr: String, port: i32) -> Self {
@@ -25,6 +26,10 @@ impl GamePlayer {
         player.set_field_type(HUMAN);
         player
     }
+
+    pub fn is_local(&self) -> bool {
+        !self.has_ip_address()
+    }
 }
 
 impl GameState {
@@ -55,6 +60,37 @@ impl GameState {
         game_state
     }
 
+    // The host of a dedicated server only runs the game, it has no snake of its own
+    pub fn new_server(name: String, ip: String, port: i32) -> GameState {
+        let mut game_state = GameState::new_custom(name, ip, port);
+        game_state.mut_snakes().clear();
+        game_state
+    }
+
+    // Adds a player sharing the host's window or a robot driven by the master, it has no address of its own
+    pub fn add_local_player(&mut self, name: String, player_type: PlayerType, config: &GameConfig) -> i32 {
+        let id = self.get_players().get_players().iter().map(|p| p.get_id()).max().unwrap_or(0) + 1;
+        let mut player = GamePlayer::default();
+        player.set_score(0);
+        player.set_id(id);
+        player.set_name(name);
+        player.set_role(NORMAL);
+        player.set_field_type(player_type);
+        self.mut_players().mut_players().push(player);
+        self.add_snake(id, config);
+        id
+    }
+
+    // Takes the snake of the robot that joined last out of the game.
+    // The robot stays in the list as a viewer, so the players after it keep their colours.
+    pub fn remove_robot(&mut self) -> Option<i32> {
+        let id = self.get_players().get_players().iter().rev()
+            .find(|p| p.get_field_type() == ROBOT && p.is_local() && p.get_role() != VIEWER)?
+            .get_id();
+        self.kill_player(id);
+        Some(id)
+    }
+
     pub fn custom_default() -> GameState {
         GameState::new_custom("random".to_string(), "0.0.0.0".to_string(), 0)
     }
@@ -73,16 +109,41 @@ impl GameState {
         return true;
     }
 
-    pub fn draw(&self, con: &Context, g: &mut G2d, config: &GameConfig) {
-        self.snakes.iter().for_each(|s| s.draw(con, g, &config));
+    // Colours go by the position in the player list, which every window gets from the master,
+    // so players of one game do not share a colour while the palette lasts
+    pub fn player_color(&self, player_id: i32) -> Color {
+        let index = self.get_players().get_players().iter().position(|p| p.get_id() == player_id)
+            .unwrap_or(player_id.unsigned_abs() as usize);
+        palette::player_color(index)
+    }
 
-        self.foods.iter().for_each(|f| draw_block(FOOD_COLOR, f.get_x(), f.get_y(), con, g));
+    pub fn draw(&self, r: &mut impl Renderer, config: &GameConfig) {
+        self.draw_interpolated(None, 1.0, r, config);
+    }
+
+    // Moves the snakes smoothly from the previous state, states further apart than one turn are drawn as they are
+    pub fn draw_interpolated(&self, previous: Option<&GameState>, progress: f64, r: &mut impl Renderer, config: &GameConfig) {
+        let previous = previous.filter(|p| p.get_state_order() + 1 == self.get_state_order());
+        if let Some(previous) = previous {
+            for snake in previous.get_snakes().iter().filter(|s| !self.get_snakes().iter().any(|c| c.get_player_id() == s.get_player_id())) {
+                snake.draw_dead(progress, previous.player_color(snake.get_player_id()), r, config);
+            }
+        }
+        for snake in self.get_snakes() {
+            match previous.and_then(|p| p.get_snakes().iter().find(|s| s.get_player_id() == snake.get_player_id())) {
+                Some(before) => snake.draw_interpolated(before, progress, self.player_color(snake.get_player_id()), r, config),
+                None => snake.draw(self.player_color(snake.get_player_id()), r, config),
+            }
+        }
+
+        self.foods.iter().for_each(|f| r.fill_cell(FOOD_COLOR, f.get_x(), f.get_y()));
 
         // Draw the border
-        draw_rectangle(BORDER_COLOR, 0, 0, config.get_width(), 1, con, g);
-        draw_rectangle(BORDER_COLOR, 0,config.get_height() - 1, config.get_width(), 1, con, g);
-        draw_rectangle(BORDER_COLOR, 0, 0, 1, config.get_height(), con, g);
-        draw_rectangle(BORDER_COLOR, config.get_width() - 1, 0, 1, config.get_height(), con, g);
+        let (width, height) = (config.get_width() as f64, config.get_height() as f64);
+        r.fill_rect(BORDER_COLOR, 0.0, 0.0, width, 1.0);
+        r.fill_rect(BORDER_COLOR, 0.0, height - 1.0, width, 1.0);
+        r.fill_rect(BORDER_COLOR, 0.0, 0.0, 1.0, height);
+        r.fill_rect(BORDER_COLOR, width - 1.0, 0.0, 1.0, height);
     }
 
     fn process_eating(&mut self, id: i32) -> bool {
@@ -181,6 +242,26 @@ impl GameState {
         }
     }
 
+    // The player is gone, its snake keeps crawling without anybody steering it
+    pub fn zombify_player(&mut self, player_id: i32) {
+        if let Some(player) = self.mut_players().mut_players().iter_mut().find(|p| p.get_id() == player_id) {
+            player.set_role(VIEWER);
+        }
+        if let Some(snake) = self.mut_snakes().iter_mut().find(|s| s.get_player_id() == player_id) {
+            snake.set_state(ZOMBIE);
+        }
+    }
+
+    // Gives a dead player a new snake, keeping its score
+    pub fn revive_player(&mut self, player_id: i32, role: NodeRole, config: &GameConfig) {
+        if let Some(player) = self.mut_players().mut_players().iter_mut().find(|p| p.get_id() == player_id && p.get_role() == VIEWER) {
+            player.set_role(role);
+            // a zombie left behind earlier is replaced
+            self.mut_snakes().retain(|s| s.get_player_id() != player_id);
+            self.add_snake(player_id, config);
+        }
+    }
+
     fn get_snake(&self, id: i32) -> &GameState_Snake {
         self.snakes.iter().find(|s| s.get_player_id() == id).unwrap()
     }
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+use crate::drawing::Renderer;
+use crate::dto::{GameConfig, GameState};
+
+// Remembers the last two states to draw the snakes moving between them
+pub struct Interpolator {
+    previous: Option<GameState>,
+    current: Option<GameState>,
+    received: Instant,
+    delay: Duration,
+}
+
+impl Interpolator {
+    pub fn new(config: &GameConfig) -> Interpolator {
+        Interpolator {
+            previous: None,
+            current: None,
+            received: Instant::now(),
+            delay: Duration::from_millis(config.get_state_delay_ms() as u64),
+        }
+    }
+
+    pub fn observe(&mut self, state: &GameState) {
+        if self.current.as_ref().is_some_and(|c| c.get_state_order() == state.get_state_order()) {
+            return;
+        }
+        self.previous = self.current.replace(state.clone());
+        self.received = Instant::now();
+    }
+
+    fn progress(&self) -> f64 {
+        (self.received.elapsed().as_secs_f64() / self.delay.as_secs_f64()).min(1.0)
+    }
+
+    pub fn draw(&self, state: &GameState, r: &mut impl Renderer, config: &GameConfig) {
+        state.draw_interpolated(self.previous.as_ref(), self.progress(), r, config);
+    }
+
+    // Where the head of the player's snake is drawn right now
+    pub fn head(&self, state: &GameState, player_id: i32, config: &GameConfig) -> Option<(f64, f64)> {
+        let snake = state.get_snakes().iter().find(|s| s.get_player_id() == player_id)?;
+        let previous = self.previous.as_ref()
+            .filter(|p| p.get_state_order() + 1 == state.get_state_order())
+            .and_then(|p| p.get_snakes().iter().find(|s| s.get_player_id() == player_id));
+        Some(match previous {
+            Some(previous) => snake.interpolated_head(previous, self.progress(), config.get_width(), config.get_height()),
+            None => (snake.get_head_position().get_x() as f64, snake.get_head_position().get_y() as f64),
+        })
+    }
+}
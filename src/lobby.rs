@@ -0,0 +1,104 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use net2::UdpBuilder;
+use net2::unix::UnixUdpBuilderExt;
+use protobuf::Message;
+use tokio::net::UdpSocket;
+
+use crate::MAX_MESSAGE_SIZE;
+use crate::dto::*;
+
+pub fn open_multicast_socket() -> UdpSocket {
+    let socket = UdpBuilder::new_v4().unwrap().reuse_port(true).unwrap().bind("0.0.0.0:9192").unwrap();
+    // tokio only accepts non-blocking sockets
+    socket.set_nonblocking(true).expect("failed to make the multicast socket non-blocking");
+
+    let multicast_socket: UdpSocket = UdpSocket::from_std(socket).unwrap();
+    multicast_socket.join_multicast_v4(Ipv4Addr::new(239, 192, 0,4), Ipv4Addr::LOCALHOST).expect("failed to join multicast group");
+    // servers bound to all interfaces announce on the default one, which fails when it is the loopback as well
+    let _ = multicast_socket.join_multicast_v4(Ipv4Addr::new(239, 192, 0,4), Ipv4Addr::UNSPECIFIED);
+    multicast_socket
+}
+
+// The address other machines reach a socket bound to `bound` at
+pub fn advertised_ip(bound: IpAddr) -> IpAddr {
+    if !bound.is_unspecified() {
+        return bound;
+    }
+    // connecting a UDP socket sends nothing, it only picks the interface the packets would leave from
+    std::net::UdpSocket::bind("0.0.0.0:0")
+        .and_then(|probe| probe.connect("239.192.0.4:9192").and_then(|_| probe.local_addr()))
+        .map_or(IpAddr::V4(Ipv4Addr::LOCALHOST), |addr| addr.ip())
+}
+
+// Waits for the first announced game, the address is the master's communication socket
+pub async fn discover_game(multicast_socket: &UdpSocket) -> (GameAnnouncement, SocketAddr) {
+    let mut buffer = vec![0; MAX_MESSAGE_SIZE];
+
+    let (bytes, sender_addr) = multicast_socket.recv_from(&mut buffer).await.expect("failed to receive GameAnnouncement");
+
+    let game_message : GameMessage = GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage");
+
+    if game_message.has_announcement() {
+        let games = &game_message.get_announcement().get_games();
+        (games.first().expect("No games found in AnnouncementMsg").clone(), sender_addr)
+    } else {
+        panic!("received not AnnouncementMsg from multicast socket")
+    }
+}
+
+// Joins the game and waits for the first state that has us in it, returns our id, the master's id and that state
+pub async fn join_game(socket: &UdpSocket, master_addr: SocketAddr, name: String, game_name: String, player_type: PlayerType) -> (i32, i32, GameState) {
+    let mut buffer = vec![0; MAX_MESSAGE_SIZE];
+
+    let join_msg: GameMessage = GameMessage::custom_new(
+        0,
+        None,
+        None,
+        GameMessage_oneof_Type::join(GameMessage_JoinMsg::custom_new(name, game_name, NodeRole::NORMAL, player_type))
+    );
+
+    //sending joining message to master
+    socket.send_to(&join_msg.write_to_bytes().expect("failed to serialize join message"), master_addr).await.expect("failed to send game message");
+
+    //receiving Acknowledge message from master
+    let msg = match socket.recv_from(&mut buffer).await {
+        Ok((bytes, _)) => GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage"),
+        Err(e) => panic!("{}", e),
+    };
+
+    let my_id:i32;
+    let master_id:i32;
+
+    if msg.has_ack() {
+        assert!(msg.has_receiver_id(), "received AckMsg don't have sender_id");
+        assert!(msg.has_sender_id(), "received AckMsg don't have master_id");
+        my_id = msg.get_receiver_id();
+        master_id = msg.get_sender_id();
+    } else if msg.has_error() {
+        panic!("the master refused to let us join: {}", msg.get_error().get_error_message())
+    } else {
+        panic!("received not AckMsg when joining to master")
+    }
+
+    loop {
+        let ping_msg: GameMessage = GameMessage::custom_new(
+            0,
+            None,
+            None,
+            GameMessage_oneof_Type::ping(GameMessage_PingMsg::default()),
+        );
+        socket.send_to(&ping_msg.write_to_bytes().expect("failed to serialize join message"), master_addr).await.expect("failed to send game message");
+
+        let state = match socket.recv_from(&mut buffer).await {
+            Ok((bytes, _)) => GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage"),
+            Err(e) => panic!("{}", e),
+        };
+
+        if state.has_state() {
+            let game_state = state.get_state().get_state();
+            if game_state.get_players().get_players().iter().any(|p| p.get_id() == my_id) {
+                return (my_id, master_id, game_state.clone());
+            }
+        }
+    }
+}
@@ -5,23 +5,48 @@ mod drawing;
 mod game_state;
 mod snakes;
 mod dto;
+mod records;
+mod controls;
+mod settings;
+mod offline;
+mod bots;
+mod lobby;
+mod netbot;
+mod external_bot;
+mod tui;
+mod snapshot;
+mod game_over;
+mod interpolation;
+mod camera;
+mod palette;
+mod notifications;
+mod delivery;
+mod window;
 
 use std::env;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use std::sync::Arc;
 use piston_window::*;
 use piston_window::types::Color;
-use protobuf::Message;
 use tokio::net::UdpSocket;
-use net2::UdpBuilder;
-use net2::unix::UnixUdpBuilderExt;
-use crate::connection::{init_master, init_slave};
+use crate::connection::{init_master, init_server, init_slave, Frontend};
+use crate::bots::{parse_robots, Difficulty, Robots};
+use crate::controls::KeyBindings;
+use crate::external_bot::ExternalBot;
+use crate::lobby::{advertised_ip, discover_game, join_game, open_multicast_socket};
+use crate::settings::Settings;
 
 use crate::drawing::*;
 use crate::dto::*;
 
 const BACK_COLOR: Color = [0.204, 0.286, 0.369, 1.0];
+const USAGE: &str = "usage: rust-snake [NAME [new]] [--offline | --server | --tui] [--hotseat SEATS] [--robots LEVELS] \
+[--network-bots LEVELS] [--external-bot COMMAND]... [--bot-budget-ms MS] [--bind IP] [--snapshot FILE]";
+// states of crowded games do not fit into small buffers
+const MAX_MESSAGE_SIZE: usize = 65_507;
 
 impl GameConfig {
     fn custom_default() -> Self {
@@ -75,11 +100,11 @@ impl GameMessage {
 }
 
 impl GameMessage_JoinMsg {
-    fn custom_new(name: String, game_name: String, requested_role: NodeRole) -> Self {
+    fn custom_new(name: String, game_name: String, requested_role: NodeRole, player_type: PlayerType) -> Self {
         let mut message: GameMessage_JoinMsg = GameMessage_JoinMsg::default();
         message.set_player_name(name);
         message.set_game_name(game_name);
-        message.set_player_type(PlayerType::HUMAN);
+        message.set_player_type(player_type);
         message.set_requested_role(requested_role);
         message
     }
@@ -109,145 +134,206 @@ impl GameMessage_AnnouncementMsg {
     }
 }
 
+impl GameMessage_ErrorMsg {
+    fn custom_new(error_message: String) -> Self {
+        let mut msg: GameMessage_ErrorMsg = GameMessage_ErrorMsg::default();
+        msg.set_error_message(error_message);
+        msg
+    }
+}
+
 #[tokio::main]
 async fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let settings = Settings::load();
+    palette::set_theme(settings.theme);
+
+    let seats = settings.controls.hotseat.len();
+    let hotseat: usize = take_option(&mut args, "--hotseat").map_or(1, |value| match value.parse() {
+        Ok(hotseat) if (1..=seats).contains(&hotseat) => hotseat,
+        _ => usage_error(&format!("--hotseat expects the number of local players, from 1 to {}", seats)),
+    });
+    let offline = take_flag(&mut args, "--offline");
+    let server = take_flag(&mut args, "--server");
+    let terminal = take_flag(&mut args, "--tui");
+    if [offline, server, terminal].into_iter().filter(|mode| *mode).count() > 1 {
+        usage_error("--offline, --server and --tui can not be combined");
+    }
+    let robot_levels: Vec<Difficulty> = take_option(&mut args, "--robots")
+        .map_or(Vec::new(), |robots| parse_robots(&robots).unwrap_or_else(|e| usage_error(&e)));
+    let network_bots: Vec<Difficulty> = take_option(&mut args, "--network-bots")
+        .map_or(Vec::new(), |bots| parse_robots(&bots).unwrap_or_else(|e| usage_error(&e)));
+    let mut external_bots: Vec<String> = Vec::new();
+    while let Some(command) = take_option(&mut args, "--external-bot") {
+        external_bots.push(command);
+    }
+    // every interface unless told otherwise, so hosts and players on other machines can reach each other
+    let bind: IpAddr = take_option(&mut args, "--bind")
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |ip| ip.parse().unwrap_or_else(|_| usage_error("--bind expects an IP address")));
+    let bot_budget: Option<Duration> = take_option(&mut args, "--bot-budget-ms")
+        .map(|ms| Duration::from_millis(ms.parse().unwrap_or_else(|_| usage_error("--bot-budget-ms expects milliseconds"))));
+
+    if let Some(recorded) = take_option(&mut args, "--snapshot") {
+        match snapshot::render_recorded(Path::new(&recorded)) {
+            Ok(png) => println!("{}", png.display()),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
 
     let name: String;
     let create: bool;
-    if args.len() == 1 {
+    if args.len() == 1 && terminal {
+        name = "Player".into();
+        create = false;
+    } else if args.len() == 1 {
         name = "Master".into();
         create = true;
     } else {
         name = args.get(1).unwrap().clone();
-        create = args.get(2).is_some();
+        create = args.get(2).is_some() || hotseat > 1 || !robot_levels.is_empty() || !external_bots.is_empty();
     }
 
-    let socket = UdpBuilder::new_v4().unwrap().reuse_port(true).unwrap().bind("0.0.0.0:9192").unwrap();
+    if terminal && (create || offline) {
+        usage_error("the terminal client can only join games");
+    }
 
+    if offline {
+        let config = GameConfig::custom_default();
+        let window = init_window(&config);
+
+        let mut game_state = GameState::new_custom(name.clone(), "0.0.0.0".into(), 0);
+        let local_players: Vec<i32> = (2..=hotseat)
+            .map(|seat| game_state.add_local_player(format!("{} {}", name, seat), PlayerType::HUMAN, &config))
+            .collect();
+        let robots = create_robots(&mut game_state, &config, &robot_levels, &external_bots, bot_budget);
 
-    let multicast_socket: UdpSocket = UdpSocket::from_std(socket).unwrap();
-    multicast_socket.join_multicast_v4(Ipv4Addr::new(239, 192, 0,4), Ipv4Addr::LOCALHOST).expect("failed to join multicast group");
+        let mut seat_bindings = KeyBindings::for_local_players(&settings.controls, hotseat);
+        let bindings = seat_bindings.remove(0);
+        offline::run_offline(window, game_state, &config, settings.font.as_deref(), bindings, local_players.into_iter().zip(seat_bindings).collect(), robots).await;
+        return;
+    }
 
+    let multicast_socket = open_multicast_socket();
+
+    if !network_bots.is_empty() {
+        let (game, master_addr) = discover_game(&multicast_socket).await;
+        let prefix = if args.len() == 1 { "Bot".to_string() } else { name };
+        netbot::run_network_bots(prefix, network_bots, game, master_addr, bind).await;
+        return;
+    }
 
-    let communication_socket =  Arc::new(Mutex::new(UdpSocket::bind("127.0.0.1:0").await.expect("failed to create communication socket")));
-    let real_addr = communication_socket.lock().await.local_addr().unwrap().clone();
+    let communication_socket =  Arc::new(Mutex::new(UdpSocket::bind(SocketAddr::new(bind, 0)).await.expect("failed to create communication socket")));
+    let real_addr = SocketAddr::new(advertised_ip(bind), communication_socket.lock().await.local_addr().unwrap().port());
 
     //todo start screen with choice connect or create
 
     println!("{}", real_addr);
 
-    if create {
+    if server {
+        let config = GameConfig::custom_default();
+        let name = if args.len() == 1 { "Server".to_string() } else { name };
+
+        let mut game_state = GameState::new_server(
+            name,
+            real_addr.ip().to_string(),
+            real_addr.port() as i32
+        );
+        let robots = create_robots(&mut game_state, &config, &robot_levels, &external_bots, bot_budget);
+
+        init_server(communication_socket, Arc::new(Mutex::new(game_state)), &config, robots).await;
+    } else if create {
         let config = GameConfig::custom_default(); //todo get
 
         // communication_socket.lock().await.connect(real_addr).await.expect("failed to connect to master"); // loopback
 
         let window = init_window(&config);
 
-        let game_state = Arc::new(Mutex::new(GameState::new_custom(
+        let mut game_state = GameState::new_custom(
             name.clone(),
             real_addr.ip().to_string(),
             real_addr.port() as i32
-        )));
+        );
+        let local_players: Vec<i32> = (2..=hotseat)
+            .map(|seat| game_state.add_local_player(format!("{} {}", name, seat), PlayerType::HUMAN, &config))
+            .collect();
+        let robots = create_robots(&mut game_state, &config, &robot_levels, &external_bots, bot_budget);
 
-        init_master(window, communication_socket, game_state, &config).await;
+        init_master(window, communication_socket, Arc::new(Mutex::new(game_state)), &config, &settings, local_players, robots).await;
     } else {
-        let mut buffer = vec![0; 2048];
-
-        let (bytes, sender_addr) = multicast_socket.recv_from(&mut buffer).await.expect("failed to receive GameAnnouncement");
-
-
-        let game_message : GameMessage = GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage");
+        let (selected, sender_addr) = discover_game(&multicast_socket).await;
+
+        let (my_id, master_id, game_state) = join_game(
+            &*communication_socket.lock().await,
+            sender_addr,
+            name,
+            selected.get_game_name().into(),
+            PlayerType::HUMAN,
+        ).await;
 
-        let selected: GameAnnouncement;
-        if game_message.has_announcement() {
-            let games = &game_message.get_announcement().get_games();
-            println!("{:?}", games);
-            selected = games.get(0).expect("No games found in AnnouncementMsg").clone();
+        let frontend = if terminal {
+            Frontend::Terminal
         } else {
-            panic!("received not AnnouncementMsg from multicast socket")
-        }
-
-        println!("1");
-
-        let join_msg: GameMessage = GameMessage::custom_new(
-            0,
-            None,
-            None,
-            GameMessage_oneof_Type::join(GameMessage_JoinMsg::custom_new(name, selected.get_game_name().into(), NodeRole::NORMAL))
-        );
-
-        //sending joining message to master
-        communication_socket.lock().await.send_to(&join_msg.write_to_bytes().expect("failed to serialize join message"), sender_addr).await.expect("failed to send game message");
+            Frontend::Window(Box::new(init_window(&selected.get_config())))
+        };
 
-        println!("sent join");
+        init_slave(frontend, communication_socket, Arc::new(Mutex::new(game_state)), master_id, my_id, &selected.get_config(), &settings).await;
+    }
+}
 
-        let msg:GameMessage;
+fn create_robots(game_state: &mut GameState, config: &GameConfig, levels: &[Difficulty], external_bots: &[String], budget: Option<Duration>) -> Robots {
+    let mut robots = Robots::default();
+    for difficulty in levels {
+        robots.add(game_state, *difficulty, config);
+    }
 
-        //receiving Acknowledge message from master
-        match communication_socket.lock().await.recv_from(&mut buffer).await {
-            Ok((bytes, _)) => {
-                msg = GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage");
-                println!("{:?}", msg);
+    // external bots get half of a turn to answer unless told otherwise
+    let budget = budget.unwrap_or(Duration::from_millis(config.get_state_delay_ms() as u64 / 2));
+    for command in external_bots {
+        let label = command.split_whitespace().last().unwrap_or(command);
+        match ExternalBot::spawn(command, budget) {
+            Ok(bot) => {
+                robots.add_with_strategy(game_state, label, Box::new(bot), config);
             },
-            Err(e) => panic!("{}", e),
-        }
-
-        let my_id:i32;
-        let master_id:i32;
-
-        println!("{:?}", msg.Type);
-
-        if msg.has_ack() {
-            assert!(msg.clone().has_receiver_id(), "received AckMsg don't have sender_id");
-            assert!(msg.clone().has_sender_id(), "received AckMsg don't have master_id");
-            my_id = msg.get_receiver_id();
-            master_id = msg.get_sender_id();
-        } else {
-            panic!("received not AckMsg when joining to master")
-        }
-
-        let mut game_state;
-        loop {
-            let ping_msg: GameMessage = GameMessage::custom_new(
-                0,
-                None,
-                None,
-                GameMessage_oneof_Type::ping(GameMessage_PingMsg::default()),
-            );
-            communication_socket.lock().await.send_to(&ping_msg.write_to_bytes().expect("failed to serialize join message"), sender_addr).await.expect("failed to send game message");
-
-            let state:GameMessage;
-            match communication_socket.lock().await.recv_from(&mut buffer).await {
-                Ok((bytes, _)) => {
-                    state = GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage");
-                    println!("{:?}", state);
-                },
-                Err(e) => panic!("{}", e),
-            }
-
-            if state.has_state() {
-                game_state = state.get_state().get_state().clone();
-                if game_state.get_players().get_players().iter().find(|p| p.get_id() == my_id).is_some() {
-                    break;
-                }
-            }
+            Err(e) => eprintln!("{}, the robot is left out", e),
         }
+    }
+    robots
+}
 
-        let window = init_window(&selected.get_config());
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let found = args.iter().any(|arg| arg == flag);
+    args.retain(|arg| arg != flag);
+    found
+}
 
-        init_slave(window, communication_socket, Arc::new(Mutex::new(game_state)), master_id, my_id, &selected.get_config()).await;
+// Removes `flag` and its value from the arguments
+fn take_option(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    if index + 1 >= args.len() {
+        usage_error(&format!("{} expects a value", flag));
     }
+    args.remove(index);
+    Some(args.remove(index))
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
 }
 
 fn init_window(config: &GameConfig) -> PistonWindow {
-    let mut window_settings = WindowSettings::new("Rust Snake",
-                                                  [to_gui_coord_f64(config.get_width())*1.5, to_gui_coord_f64(config.get_height())]);
+    let mut window_settings = WindowSettings::new("Rust Snake", initial_window_size(config));
 
     // Fix vsync extension error for linux
     window_settings.set_vsync(true);
 
-    window_settings.exit_on_esc(true)
+    // leaving is bound through the key bindings
+    window_settings.exit_on_esc(false)
+        .resizable(true)
         .graphics_api(OpenGL::V3_2)
         .build()
         .unwrap()
@@ -259,7 +345,7 @@ pub(crate) mod connection {
     extern crate serde;
     extern crate tokio;
 
-    use std::collections::{HashMap, HashSet};
+    use std::collections::HashMap;
     use std::net::SocketAddr;
     use tokio::sync::Mutex;
     use std::sync::Arc;
@@ -271,32 +357,39 @@ pub(crate) mod connection {
     use rand::random;
     use tokio::net::UdpSocket;
     use tokio::task::JoinHandle;
-    use tokio::time::{interval, sleep};
-    use crate::BACK_COLOR;
+    use tokio::time::{interval, timeout};
+    use crate::MAX_MESSAGE_SIZE;
 
     use crate::connection::send::*;
-    use crate::drawing::to_gui_coord_f64;
     use crate::dto::*;
     use crate::dto::NodeRole::{DEPUTY, MASTER, NORMAL, VIEWER};
+    use crate::controls::KeyBindings;
+    use crate::settings::Settings;
+    use crate::delivery::{Deliveries, Delivery, Liveness, SeenMessages};
+    use crate::notifications::{self, Notifier};
+    use crate::bots::{Difficulty, Robots};
+    use crate::tui::terminal_loop;
+    use crate::window::{run_window, Session};
 
     static MY_ID: AtomicI32 = AtomicI32::new(1);
     static MASTER_ID: AtomicI32 = AtomicI32::new(1);
     static COUNTER: AtomicI64 = AtomicI64::new(1); //zero was when we tried to connect
 
     mod send {
-        use std::collections::{HashMap, HashSet};
+        use std::net::SocketAddr;
         use std::sync::Arc;
         use std::sync::atomic::Ordering::Relaxed;
-        use std::time::Duration;
         use protobuf::Message;
         use tokio::net::UdpSocket;
-        use tokio::sync::Mutex;
-        use tokio::time::sleep;
+        use tokio::sync::{oneshot, Mutex};
+        use tokio::time::interval;
         use crate::connection::{COUNTER, MY_ID};
+        use crate::delivery::{Deliveries, Delivery};
         use crate::dto::{GameMessage, GameMessage_AckMsg, GameMessage_oneof_Type, GamePlayer, GamePlayers};
 
-        pub(super) async fn send_to_all(socket: Arc<Mutex<UdpSocket>>, game_message_type: GameMessage_oneof_Type, game_players: &GamePlayers, awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>) {
-            for player in game_players.get_players() {
+        pub(super) async fn send_to_all(socket: Arc<Mutex<UdpSocket>>, game_message_type: GameMessage_oneof_Type, game_players: &GamePlayers, deliveries: Deliveries) {
+            // local players share the master's window, the master keeps its states without sending them to itself
+            for player in game_players.get_players().iter().filter(|p| !p.is_local() && p.get_id() != MY_ID.load(Relaxed)) {
                 assert!(player.has_ip_address(), "{}", format!("missing ip_addr field from {} player", player.get_name()));
                 assert!(player.has_port(), "{}", format!("missing port field from {} player", player.get_name()));
                 send_game_message(
@@ -305,12 +398,13 @@ pub(crate) mod connection {
                     Some(player.get_id()),
                     player.get_ip_address().into(),
                     player.get_port(),
-                    awaiting_packages.clone()
+                    deliveries.clone()
                 ).await;
             }
         }
 
-        pub(super) async fn send_game_message(socket: Arc<Mutex<UdpSocket>>, game_message_type: GameMessage_oneof_Type, receiver_id: Option<i32>, ip: String, port: i32, awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>) {
+        // Resends the message until the receiver acks it or is declared dead, the result tells which one happened
+        pub(super) async fn send_game_message(socket: Arc<Mutex<UdpSocket>>, game_message_type: GameMessage_oneof_Type, receiver_id: Option<i32>, ip: String, port: i32, deliveries: Deliveries) -> oneshot::Receiver<Delivery> {
             let message = GameMessage::custom_new(
                 COUNTER.fetch_add(1, Relaxed),
                 Some(MY_ID.load(Relaxed)),
@@ -319,23 +413,40 @@ pub(crate) mod connection {
             );
 
             let bytes = message.write_to_bytes().expect("failed to serialize the GameMessage");
+            let addr: SocketAddr = format!("{ip}:{port}").parse().expect("invalid peer address");
+
+            let Some(receiver_id) = receiver_id else {
+                let _ = socket.lock().await.try_send_to(&bytes, addr);
+                let (sent, result) = oneshot::channel();
+                let _ = sent.send(Delivery::Sent);
+                return result;
+            };
+            // a peer declared dead gets the message once, resending it would flood a window that is gone
+            if deliveries.is_dead(receiver_id).await {
+                let _ = socket.lock().await.try_send_to(&bytes, addr);
+                deliveries.sent(receiver_id).await;
+                let (sent, result) = oneshot::channel();
+                let _ = sent.send(Delivery::Failed);
+                return result;
+            }
 
-            // Send the JSON string through the UDP socket
+            let result = deliveries.track(receiver_id, message.get_msg_seq()).await;
             tokio::spawn(async move {
-                let delay = Duration::from_micros(10); //todo 0.1 * state_delay_ms
-                for _ in 0..8 {
-                    let _ = socket.lock().await.try_send_to(&bytes, format!("{ip}:{port}").parse().unwrap());
-                    sleep(delay).await;
-                    if receiver_id.is_some() {
-                        if awaiting_packages.lock().await.get_mut(&receiver_id.unwrap()).expect("awaiting_packages for player not initialized").remove(&message.get_msg_seq()) {
-                            break;
-                        }
+                // the first tick completes right away
+                let mut resend = interval(deliveries.resend_interval());
+                loop {
+                    resend.tick().await;
+                    if !deliveries.is_in_flight(receiver_id, message.get_msg_seq()).await {
+                        return;
                     }
+                    let _ = socket.lock().await.try_send_to(&bytes, addr);
+                    deliveries.sent(receiver_id).await;
                 }
             });
+            result
         }
 
-        pub(super) async fn send_ack_message(socket: Arc<Mutex<UdpSocket>>, msg_seq: i64, sender: GamePlayer) {
+        pub(super) async fn send_ack_message(socket: Arc<Mutex<UdpSocket>>, deliveries: Deliveries, msg_seq: i64, sender: GamePlayer) {
             let message: GameMessage = GameMessage::custom_new(
                 msg_seq,
                 Some(MY_ID.load(Relaxed)),
@@ -344,174 +455,283 @@ pub(crate) mod connection {
             );
             let bytes = message.write_to_bytes().expect("failed to serialize ack message");
             socket.lock().await.send_to(&*bytes, format!("{}:{}", sender.get_ip_address(), sender.get_port())).await.expect("failed to send ack message");
+            deliveries.sent(sender.get_id()).await;
         }
     }
 
-    fn init_awaiting_packages(players: &[GamePlayer]) -> Arc<Mutex<HashMap<i32, HashSet<i64>>>> {
-        let mut tmp = HashMap::new();
-        for player in players {
-            tmp.insert(player.get_id(), HashSet::new());
-        }
-        return Arc::new(Mutex::new(tmp));
-    }
-
-    fn init_alive_players(players: &[GamePlayer]) -> Arc<Mutex<HashSet<i32>>> {
-        let mut tmp = HashSet::new();
-        for player in players {
-            tmp.insert(player.get_id());
+    // Pings the peers nothing was sent to for a tenth of a turn, so they do not take us for dead
+    async fn pinger(socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, deliveries: Deliveries) {
+        let mut interval = interval(deliveries.resend_interval());
+        loop {
+            interval.tick().await;
+            let players = game_state.lock().await.get_players().clone();
+            for player in players.get_players() {
+                if player.get_id() == MY_ID.load(SeqCst) || player.is_local() {
+                    continue;
+                }
+                // the master talks to everybody, the others only to the master
+                if MY_ID.load(SeqCst) != MASTER_ID.load(SeqCst) && player.get_id() != MASTER_ID.load(SeqCst) {
+                    continue;
+                }
+                // players the master gave up on (like viewers who closed their window) would be pinged forever,
+                // a master gone silent is pinged on until it answers again
+                if deliveries.is_dead(player.get_id()).await && player.get_role() == VIEWER {
+                    continue;
+                }
+                if deliveries.idle_for(player.get_id()).await >= deliveries.resend_interval() {
+                    send_game_message(
+                        socket.clone(),
+                        GameMessage_oneof_Type::ping(GameMessage_PingMsg::default()),
+                        Some(player.get_id()),
+                        player.get_ip_address().into(),
+                        player.get_port(),
+                        deliveries.clone(),
+                    ).await;
+                }
+            }
         }
-        return Arc::new(Mutex::new(tmp));
     }
 
-    pub(super) async fn init_master(window: PistonWindow, socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, config: &GameConfig) {
+    pub(super) async fn init_master(window: PistonWindow, socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, config: &GameConfig, settings: &Settings, local_players: Vec<i32>, robots: Robots) {
         let my_id = game_state.lock().await.get_players().get_players().get(0).unwrap().get_id();
         MASTER_ID.store(my_id, SeqCst);
         MY_ID.store(my_id, SeqCst);
 
-        let awaiting_packages = init_awaiting_packages(game_state.lock().await.get_players().get_players());
+        let deliveries = Deliveries::new(config);
+        let moves: Arc<Mutex<HashMap<i32, Direction>>> = Arc::new(Mutex::new(HashMap::new()));
+        let robots = Arc::new(Mutex::new(robots));
+        let (notifier, toasts) = notifications::channel();
 
         master_communication_controller(
             game_state.clone(),
             socket.clone(),
-            awaiting_packages.clone(),
+            moves.clone(),
+            robots.clone(),
+            deliveries.clone(),
             config.clone(),
+            notifier.clone(),
         ).await;
 
-        event_loop(
-            window,
-            socket.clone(),
+        let mut seat_bindings = KeyBindings::for_local_players(&settings.controls, local_players.len() + 1);
+        let bindings = seat_bindings.remove(0);
+        let local = Some(LocalPlayers { seats: local_players.into_iter().zip(seat_bindings).collect(), moves, robots });
+
+        let session = NetworkSession { socket, game_state, deliveries, config: config.clone(), notifier, local };
+        run_window(window, session, config, bindings, settings.font.as_deref(), toasts).await;
+    }
+
+    // Runs the game without a window until the process is stopped
+    pub(super) async fn init_server(socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, config: &GameConfig, robots: Robots) {
+        let my_id = game_state.lock().await.get_players().get_players()[0].get_id();
+        MASTER_ID.store(my_id, SeqCst);
+        MY_ID.store(my_id, SeqCst);
+
+        let deliveries = Deliveries::new(config);
+        // nobody looks at a server, its notices are dropped
+        let (notifier, _) = notifications::channel();
+
+        master_communication_controller(
             game_state.clone(),
-            awaiting_packages.clone(),
-            config
+            socket.clone(),
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(Mutex::new(robots)),
+            deliveries.clone(),
+            config.clone(),
+            notifier,
         ).await;
+
+        println!("serving {}", game_state.lock().await.generate_announcement(config.clone()).get_game_name());
+        tokio::signal::ctrl_c().await.expect("failed to wait for ctrl-c");
     }
 
     async fn master_killer(game_state: Arc<Mutex<GameState>>, config: GameConfig, handle: JoinHandle<()>) {
         let mut interval = interval(Duration::from_millis(config.get_state_delay_ms() as u64));
         loop {
             interval.tick().await;
-            if game_state.lock().await.get_players().get_players().iter().find(|p| p.get_id() == MY_ID.load(SeqCst)).unwrap().get_role() == VIEWER {
+            if game_state.lock().await.get_players().get_players().iter()
+                .filter(|p| p.get_id() == MY_ID.load(SeqCst) || (p.is_local() && p.get_field_type() == PlayerType::HUMAN))
+                .all(|p| p.get_role() == VIEWER) {
                 handle.abort();
                 return;
             }
         }
     }
 
-    pub(super) async fn init_slave(window: PistonWindow, socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, master_id:i32, slave_id:i32, config: &GameConfig) {
+    pub(crate) enum Frontend {
+        Window(Box<PistonWindow>),
+        Terminal,
+    }
+
+    pub(super) async fn init_slave(frontend: Frontend, socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, master_id:i32, slave_id:i32, config: &GameConfig, settings: &Settings) {
         MASTER_ID.store(master_id, SeqCst);
         MY_ID.store(slave_id, SeqCst);
 
-        let awaiting_packages = init_awaiting_packages(game_state.lock().await.get_players().get_players());
+        let deliveries = Deliveries::new(config);
+        let (notifier, toasts) = notifications::channel();
 
         tokio::spawn(slave_communication_controller(
             game_state.clone(),
             socket.clone(),
-            awaiting_packages.clone(),
-            config.clone()
+            deliveries.clone(),
+            config.clone(),
+            notifier.clone(),
         ));
 
-        event_loop(
-            window,
-            socket.clone(),
-            game_state.clone(),
-            awaiting_packages.clone(),
-            config
-        ).await;
+        let bindings = KeyBindings::from_settings(&settings.controls);
+        match frontend {
+            Frontend::Window(window) => {
+                let session = NetworkSession { socket, game_state, deliveries, config: config.clone(), notifier, local: None };
+                run_window(*window, session, config, bindings, settings.font.as_deref(), toasts).await;
+            },
+            // the terminal is in raw mode, every notice goes to its status lines instead of stdout
+            Frontend::Terminal => terminal_loop(
+                socket.clone(),
+                game_state.clone(),
+                deliveries.clone(),
+                config,
+                bindings,
+                toasts,
+                slave_id
+            ).await,
+        }
     }
 
-    async fn event_loop(
-        mut window: PistonWindow,
+    // The window of a player, its turns are played by the master
+    struct NetworkSession {
         socket: Arc<Mutex<UdpSocket>>,
         game_state: Arc<Mutex<GameState>>,
-        awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>,
-        config: &GameConfig,
-    ) {
-        // Create a Glyphs object for rendering text
-        let mut glyphs = window.load_font("/home/macsia/Downloads/RustRover-233.10527.212/jbr/lib/fonts/DroidSans.ttf").unwrap();
-
-        while let Some(event) = window.next() {
-            // Catch the events of the keyboard
-            if let Some(Button::Keyboard(key)) = event.press_args() {
-                tokio::spawn(key_handler(
-                    key,
-                    socket.clone(),
-                    game_state.clone(),
-                    awaiting_packages.clone(),
-                ));
-            }
+        deliveries: Deliveries,
+        config: GameConfig,
+        notifier: Notifier,
+        local: Option<LocalPlayers>,
+    }
 
-            let state = game_state.lock().await.clone();
+    impl Session for NetworkSession {
+        fn my_id(&self) -> i32 {
+            MY_ID.load(SeqCst)
+        }
 
-            // Draw all of them
-            window.draw_2d(&event, |c, g, device| {
-                clear(BACK_COLOR, g);
+        async fn state(&mut self) -> GameState {
+            self.game_state.lock().await.clone()
+        }
 
-                // Draw the main game content
-                state.draw(&c, g, config);
+        async fn steer_seats(&mut self, key: Key) {
+            if let Some(local) = &self.local {
+                local.steer(key).await;
+            }
+        }
 
-                // todo Draw the side panel with player information
-                draw_side_panel(&c, g, config, &mut glyphs, &state);
-                glyphs.factory.encoder.flush(device);
-            });
+        async fn steer(&mut self, dir: Direction) {
+            tokio::spawn(key_handler(
+                dir,
+                self.socket.clone(),
+                self.game_state.clone(),
+                self.deliveries.clone(),
+            ));
         }
-    }
 
-    fn draw_side_panel(c: &Context, g: &mut G2d, config: &GameConfig, glyphs: &mut Glyphs, state: &GameState) {
-        // Define the side panel dimensions
-        let side_panel_width = to_gui_coord_f64(config.get_width()) / 2.0;
-        let side_panel_height = to_gui_coord_f64(config.get_height());
+        // only the master drives robots
+        async fn add_robot(&mut self, difficulty: Difficulty) {
+            if let Some(local) = &self.local {
+                local.robots.lock().await.add(&mut *self.game_state.lock().await, difficulty, &self.config);
+            }
+        }
+
+        async fn remove_robot(&mut self) {
+            if let Some(local) = &self.local {
+                local.robots.lock().await.remove(&mut *self.game_state.lock().await);
+            }
+        }
 
-        // Set the color for the side panel background
-        let side_panel_color = [0.9, 0.9, 0.9, 1.0];
+        // the master's game ends with its snake, only the others can come back
+        fn can_rejoin(&self) -> bool {
+            self.local.is_none()
+        }
 
-        // Draw the side panel background
-        rectangle(
-            side_panel_color,
-            [to_gui_coord_f64(config.get_width()), 0.0, side_panel_width, side_panel_height],
-            c.transform,
-            g,
-        );
+        async fn rejoin(&mut self) {
+            tokio::spawn(rejoin(self.socket.clone(), self.game_state.clone(), self.deliveries.clone(), self.config.clone(), self.notifier.clone()));
+        }
 
-        // Set the color for the text
-        let text_color = [0.0, 0.0, 0.0, 1.0];
-
-        // Set the font size
-        let font_size = 15;
-
-        // Draw player information on the side panel
-        let mut y_position = 40.0; // Start with an initial Y position
-        for player in state.get_players().get_players() {
-            text(
-                text_color,
-                font_size,
-                &format!("{}({:?}), score: {}", player.get_name(), player.get_role(), player.get_score()),
-                glyphs,
-                c.transform.trans(to_gui_coord_f64(config.get_width()) + 40.0, y_position),
-                g,
-            ).expect("failed to draw side panel");
-            y_position += 30.0; // Adjust the spacing between player information
+        async fn leave(&mut self) {
+            if self.local.is_none() {
+                leave(self.socket.clone(), self.game_state.clone(), self.deliveries.clone(), &self.config).await;
+            }
         }
     }
 
+    // Tells the master we are gone, so it lets our snake go without waiting for a timeout
+    pub(crate) async fn leave(socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, deliveries: Deliveries, config: &GameConfig) {
+        // the master is the one who would have to be told
+        if MY_ID.load(SeqCst) == MASTER_ID.load(SeqCst) {
+            return;
+        }
+        let Some(master) = game_state.lock().await.get_players().get_players().iter().find(|p| p.get_id() == MASTER_ID.load(SeqCst)).cloned() else {
+            return;
+        };
 
-    async fn key_handler(key: Key, communication_socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>) {
-        let dir = match key {
-            Key::Up => Some(Direction::UP),
-            Key::W => Some(Direction::UP),
+        let mut message = GameMessage_RoleChangeMsg::default();
+        message.set_sender_role(VIEWER);
+        let delivery = send_game_message(
+            socket,
+            GameMessage_oneof_Type::role_change(message),
+            Some(master.get_id()),
+            master.get_ip_address().into(),
+            master.get_port(),
+            deliveries,
+        ).await;
+        // the window is already closed, an ack that takes longer than a turn is not waited for
+        let _ = timeout(Duration::from_millis(config.get_state_delay_ms() as u64), delivery).await;
+    }
 
-            Key::Down => Some(Direction::DOWN),
-            Key::S => Some(Direction::DOWN),
+    // Players sharing the master's window and the robots it drives, their moves skip the network
+    struct LocalPlayers {
+        seats: Vec<(i32, KeyBindings)>,
+        moves: Arc<Mutex<HashMap<i32, Direction>>>,
+        robots: Arc<Mutex<Robots>>,
+    }
 
-            Key::Left => Some(Direction::LEFT),
-            Key::A => Some(Direction::LEFT),
+    impl LocalPlayers {
+        async fn steer(&self, key: Key) {
+            for (player_id, bindings) in &self.seats {
+                if let Some(dir) = bindings.action(key).and_then(|a| a.direction()) {
+                    self.moves.lock().await.insert(*player_id, dir);
+                }
+            }
+        }
+    }
 
-            Key::Right => Some(Direction::RIGHT),
-            Key::D => Some(Direction::RIGHT),
-            // Ignore other keys
-            _ => return,
+    // Asks the master for a new snake after ours died
+    async fn rejoin(socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, deliveries: Deliveries, config: GameConfig, notifier: Notifier) {
+        let state = game_state.lock().await.clone();
+        let players = state.get_players().get_players();
+        let (Some(master), Some(me)) = (
+            players.iter().find(|p| p.get_id() == MASTER_ID.load(Relaxed)),
+            players.iter().find(|p| p.get_id() == MY_ID.load(Relaxed)),
+        ) else {
+            return;
         };
 
-        let steer_msg = GameMessage_SteerMsg::custom_new(dir.unwrap());
+        let join_msg = GameMessage_JoinMsg::custom_new(
+            me.get_name().into(),
+            state.generate_announcement(config).get_game_name().into(),
+            NORMAL,
+            PlayerType::HUMAN,
+        );
+        let delivery = send_game_message(
+            socket,
+            GameMessage_oneof_Type::join(join_msg),
+            Some(master.get_id()),
+            master.get_ip_address().into(),
+            master.get_port(),
+            deliveries,
+        ).await;
+        if delivery.await == Ok(Delivery::Failed) {
+            notifier.error("Could not rejoin, the master does not answer");
+        }
+    }
+
+    pub(crate) async fn key_handler(dir: Direction, communication_socket: Arc<Mutex<UdpSocket>>, game_state: Arc<Mutex<GameState>>, deliveries: Deliveries) {
+        let steer_msg = GameMessage_SteerMsg::custom_new(dir);
 
         if let Some(master) = game_state.lock().await.get_players().get_players().iter().find(|p| p.get_id()==MASTER_ID.load(Relaxed)).clone() {
             send_game_message(
@@ -520,7 +740,7 @@ pub(crate) mod connection {
                 Some(master.get_id()),
                 master.get_ip_address().into(),
                 master.get_port(),
-                awaiting_packages.clone(),
+                deliveries.clone(),
             ).await;
         }
     }
@@ -528,19 +748,22 @@ pub(crate) mod connection {
     async fn master_communication_controller(
         game_state: Arc<Mutex<GameState>>,
         communication_socket: Arc<Mutex<UdpSocket>>,
-        awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>,
+        moves: Arc<Mutex<HashMap<i32, Direction>>>,
+        robots: Arc<Mutex<Robots>>,
+        deliveries: Deliveries,
         config: GameConfig,
+        notifier: Notifier,
     ) {
-        let moves: Arc<Mutex<HashMap<i32, Direction>>> = Arc::new(Mutex::new(HashMap::new()));
-        let players_alive = init_alive_players(game_state.lock().await.get_players().get_players());
+        let liveness = Liveness::new(&config, game_state.lock().await.get_players().get_players());
 
         let _request_controller_handle = tokio::spawn(request_controller(
             game_state.clone(),
             communication_socket.clone(),
             moves.clone(),
-            awaiting_packages.clone(),
+            deliveries.clone(),
             config.clone(),
-            players_alive.clone()
+            liveness.clone(),
+            notifier.clone(),
         ));
 
         let _announce_translator_handle = tokio::spawn(announce_translator(
@@ -553,47 +776,67 @@ pub(crate) mod connection {
             game_state.clone(),
             communication_socket.clone(),
             moves.clone(),
-            awaiting_packages.clone(),
+            robots.clone(),
+            deliveries.clone(),
             config.clone(),
-            players_alive.clone()
+            liveness.clone(),
+            notifier,
         ));
 
+        let _pinger_handle = tokio::spawn(pinger(communication_socket.clone(), game_state.clone(), deliveries.clone()));
+
         tokio::spawn(master_killer(game_state.clone(), config.clone(), _request_controller_handle));
         tokio::spawn(master_killer(game_state.clone(), config.clone(), _announce_translator_handle));
         tokio::spawn(master_killer(game_state.clone(), config.clone(), _game_turn_controller_handle));
+        tokio::spawn(master_killer(game_state.clone(), config.clone(), _pinger_handle));
 
     }
 
     async fn slave_communication_controller(
         game_state: Arc<Mutex<GameState>>,
         communication_socket: Arc<Mutex<UdpSocket>>,
-        awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>,
-        config: GameConfig
+        deliveries: Deliveries,
+        config: GameConfig,
+        notifier: Notifier,
     ) {
         let moves: Arc<Mutex<HashMap<i32, Direction>>> = Arc::new(Mutex::new(HashMap::new()));
-        let alive_players = init_alive_players(game_state.lock().await.get_players().get_players());
+        let liveness = Liveness::new(&config, game_state.lock().await.get_players().get_players());
         let request_controller_handle = tokio::spawn(request_controller(
             game_state.clone(),
             communication_socket.clone(),
             moves.clone(),
-            awaiting_packages.clone(),
+            deliveries.clone(),
             config.clone(),
-            alive_players.clone()
+            liveness.clone(),
+            notifier.clone(),
         ));
+        let pinger_handle = tokio::spawn(pinger(communication_socket.clone(), game_state.clone(), deliveries.clone()));
 
-        let delay = Duration::from_secs_f32(config.get_state_delay_ms() as f32 / 1000f32 * 2.0);
+        let mut interval = interval(deliveries.resend_interval());
+        // told once every time the master stops answering
+        let mut master_silent = false;
         loop {
-            alive_players.lock().await.clear();
-            sleep(delay).await;
-            // nothing received from master for interval
-            if alive_players.lock().await.get(&MASTER_ID.load(SeqCst)).is_none() {
-                println!("it's my star time");
+            interval.tick().await;
+            // nothing received from master for 0.8 of a turn
+            if liveness.is_alive(MASTER_ID.load(SeqCst)).await {
+                master_silent = false;
+            } else {
+                deliveries.declare_dead(MASTER_ID.load(SeqCst)).await;
                 if game_state.lock().await.get_players().get_players().iter().find(|p| p.get_id() == MY_ID.load(SeqCst)).unwrap().get_role() == DEPUTY {
                     request_controller_handle.abort();
+                    pinger_handle.abort();
 
                     let mut game_state_copy = game_state.lock().await.clone();
 
-                    game_state_copy.mut_players().mut_players().iter_mut().find(|p| p.get_id() == MASTER_ID.load(SeqCst)).unwrap().set_role(VIEWER);
+                    game_state_copy.zombify_player(MASTER_ID.load(SeqCst));
+                    // the old host's hot-seat players went with its window, nobody steers their snakes any more
+                    let seats: Vec<i32> = game_state_copy.get_players().get_players().iter()
+                        .filter(|p| p.is_local() && p.get_field_type() == PlayerType::HUMAN)
+                        .map(|p| p.get_id())
+                        .collect();
+                    for player_id in seats {
+                        game_state_copy.zombify_player(player_id);
+                    }
 
                     game_state_copy.mut_players().mut_players().iter_mut().find(|p| p.get_id() == MY_ID.load(SeqCst)).unwrap().set_role(MASTER);
 
@@ -601,13 +844,17 @@ pub(crate) mod connection {
 
                     MASTER_ID.store(MY_ID.load(SeqCst), SeqCst);
 
-                    awaiting_packages.lock().await.insert(MASTER_ID.load(SeqCst), HashSet::new());
-
-                    println!("now i master)");
+                    notifier.info("The master is gone, you are the master now");
 
-                    master_communication_controller(game_state, communication_socket, awaiting_packages, config.clone()).await;
+                    // the robots keep playing, but this window was opened without the host's keys to add or remove them
+                    let robots = Robots::inherit(&*game_state.lock().await);
+                    master_communication_controller(game_state, communication_socket, moves, Arc::new(Mutex::new(robots)), deliveries, config.clone(), notifier).await;
                     return;
                 }
+                if !master_silent {
+                    master_silent = true;
+                    notifier.error("The master does not answer, waiting for it");
+                }
             }
         }
     }
@@ -616,12 +863,14 @@ pub(crate) mod connection {
         game_state: Arc<Mutex<GameState>>,
         communication_socket: Arc<Mutex<UdpSocket>>,
         moves:Arc<Mutex<HashMap<i32, Direction>>>,
-        awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>,
+        deliveries: Deliveries,
         config: GameConfig,
-        players_alive: Arc<Mutex<HashSet<i32>>>,
+        liveness: Liveness,
+        notifier: Notifier,
     ) {
-        let mut buffer = vec![0; 2048];
+        let mut buffer = vec![0; MAX_MESSAGE_SIZE];
         let mut interval = interval(Duration::from_micros(10));
+        let mut seen = SeenMessages::default();
 
         loop {
             let result = communication_socket.lock().await.try_recv_from(&mut buffer);
@@ -631,23 +880,30 @@ pub(crate) mod connection {
                     let game_message = GameMessage::parse_from_bytes(&buffer[..bytes]).expect("failed to deserialize GameMessage");
                     let sender = find_player_id_by_ip(game_state.clone(), addr).await;
 
-                    if !game_message.has_state() && !game_message.has_ack() {
-                        println!("{:?}", &game_message.clone().Type.unwrap());
+                    if !game_message.has_join() {
+                        let sender_id = sender.clone().expect("should be here...").get_id();
+                        liveness.saw(sender_id).await;
+                        deliveries.heard_from(sender_id).await;
                     }
 
-                    if !game_message.has_join() {
-                        players_alive.lock().await.insert(sender.clone().expect("should be here...").get_id());
+                    // acks carry our own msg_seq, announcements and discovers are never acked
+                    let acked = !(game_message.has_ack() || game_message.has_announcement() || game_message.has_discover());
+                    if acked && !seen.first_time(addr, game_message.get_msg_seq()) {
+                        if let Some(sender) = sender {
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), sender).await;
+                        }
+                        continue;
                     }
 
                     match game_message.Type.clone().unwrap() {
                         GameMessage_oneof_Type::ping(_converted) => {
                             // если мы ничего не отправляли в течении GameTurn нужно отправить его
-                            send_ack_message(communication_socket.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
                         },
                         GameMessage_oneof_Type::steer(converted) => {
                             //получаем новое направление от игрока
                             moves.lock().await.insert(sender.clone().unwrap().get_id(), converted.get_direction());
-                            send_ack_message(communication_socket.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
                         },
                         GameMessage_oneof_Type::ack(_converted) => {
                             //знаем что можно не пересылать сообщение с game_message.msg_seq
@@ -655,15 +911,19 @@ pub(crate) mod connection {
                             assert!(&game_message.clone().has_sender_id(), "protocol asserts GameMessage to have sender_id");
                             assert_eq!(sender.clone().unwrap().get_id(), game_message.clone().get_sender_id(), "only allow to receive AckMessages from Master");
 
-                            awaiting_packages.lock().await.get_mut(&sender.unwrap().get_id()).unwrap().insert(game_message.get_msg_seq());
+                            deliveries.ack(sender.unwrap().get_id(), game_message.get_msg_seq()).await;
                         },
                         GameMessage_oneof_Type::state(converted) => {
                             //cохраняем новое состояние
-                            if (game_state.lock().await.get_state_order() >= converted.get_state().get_state_order()) || (sender.clone().unwrap().get_id() != MASTER_ID.load(Relaxed)) {
+                            if sender.clone().unwrap().get_id() != MASTER_ID.load(Relaxed) {
                                 continue;
                             }
-                            game_state.lock().await.clone_from(&converted.get_state());
-                            send_ack_message(communication_socket.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
+                            // outdated states are acked as well, or the master would keep resending them
+                            if game_state.lock().await.get_state_order() < converted.get_state().get_state_order() {
+                                notifier.player_changes(&*game_state.lock().await, converted.get_state(), MY_ID.load(Relaxed));
+                                game_state.lock().await.clone_from(&converted.get_state());
+                            }
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
                         },
                         GameMessage_oneof_Type::announcement(_converted) => {
                             //ignored because we will not send discover while playing
@@ -671,38 +931,61 @@ pub(crate) mod connection {
                         GameMessage_oneof_Type::join(converted) => {
                             //добавляем игрока в игру
                             assert_eq!(game_state.lock().await.generate_announcement(config.clone()).get_game_name(), converted.get_game_name(), "checks the game_name param in JoinMsg");
+                            // only the master hands out its own role and the deputy's, everybody else plays or watches
+                            if converted.get_requested_role() == MASTER {
+                                let error = GameMessage_ErrorMsg::custom_new("Nobody can join as the master".into());
+                                send_game_message(communication_socket.clone(), GameMessage_oneof_Type::error(error), None, addr.ip().to_string(), addr.port() as i32, deliveries.clone()).await;
+                                if let Some(known) = sender {
+                                    send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), known).await;
+                                }
+                                continue;
+                            }
+                            let role = if converted.get_requested_role() == VIEWER { VIEWER } else { NORMAL };
+                            // a dead player asks to play again, the toast comes with the next state like for everybody else
+                            if let Some(known) = sender {
+                                if role == NORMAL {
+                                    game_state.lock().await.revive_player(known.get_id(), NORMAL, &config);
+                                }
+                                liveness.saw(known.get_id()).await;
+                                deliveries.heard_from(known.get_id()).await;
+                                send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), known).await;
+                                continue;
+                            }
                             let player = GamePlayer::custom_new(
                                 converted.get_player_name().to_string(),
                                 random(), //todo generate with id generator
                                 addr.ip().to_string(),
                                 addr.port() as i32,
-                                converted.get_requested_role(),
+                                role,
                                 converted.get_player_type(),
                                 0,
                             );
-                            awaiting_packages.lock().await.insert(player.get_id().clone(), HashSet::new());
-                            players_alive.lock().await.insert(player.get_id().clone());
+                            liveness.saw(player.get_id()).await;
                             game_state.lock().await.mut_players().mut_players().push(player.clone());
-                            game_state.lock().await.add_snake(player.get_id(), &config);
-                            send_ack_message(communication_socket.clone(), game_message.get_msg_seq(), player).await;
+                            if role == NORMAL {
+                                game_state.lock().await.add_snake(player.get_id(), &config);
+                            }
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), player).await;
                         },
                         GameMessage_oneof_Type::error(converted) => {
                             // отобразить его на экране, не блокируя работу программы
-                            // todo new window
-                            eprint!("{}", converted.get_error_message());
-                            send_ack_message(communication_socket.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
+                            notifier.error(converted.get_error_message());
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
                         },
                         GameMessage_oneof_Type::role_change(converted) => {
                             //cменить отправителя по умолчанию для сокета...назначить нового депути
                             if MASTER_ID.load(SeqCst) == MY_ID.load(SeqCst) {
-                                // если мы мастер то игнорим)
+                                // a player leaves the game, everything else is ignored by the master
+                                if let (VIEWER, Some(leaving)) = (converted.get_sender_role(), sender) {
+                                    liveness.left(leaving.get_id()).await;
+                                    send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), leaving).await;
+                                }
                                 continue;
                             }
                             if converted.get_sender_role() == MASTER {
                                 MASTER_ID.store(game_message.get_sender_id(), Relaxed);
-                                awaiting_packages.lock().await.insert(MASTER_ID.load(SeqCst), HashSet::new());
                             }
-                            send_ack_message(communication_socket.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
+                            send_ack_message(communication_socket.clone(), deliveries.clone(), game_message.get_msg_seq(), sender.unwrap()).await;
                             // todo!("смена роли");
                         },
                         GameMessage_oneof_Type::discover(_converted) => {
@@ -715,7 +998,7 @@ pub(crate) mod connection {
                                 None,
                                 addr.ip().to_string(),
                                 addr.port() as i32,
-                                awaiting_packages.clone(),
+                                deliveries.clone(),
                             ).await;
                         },
                     }
@@ -734,39 +1017,61 @@ pub(crate) mod connection {
 
 
     //by master
+    #[allow(clippy::too_many_arguments)]
     async fn game_turn_controller(
         game_state: Arc<Mutex<GameState>>,
         communication_socket: Arc<Mutex<UdpSocket>>,
         moves: Arc<Mutex<HashMap<i32, Direction>>>,
-        awaiting_packages: Arc<Mutex<HashMap<i32, HashSet<i64>>>>,
+        robots: Arc<Mutex<Robots>>,
+        deliveries: Deliveries,
         config: GameConfig,
-        players_alive: Arc<Mutex<HashSet<i32>>>
+        liveness: Liveness,
+        notifier: Notifier,
     ) {
         let delay = Duration::from_millis(config.get_state_delay_ms() as u64);
         let mut interval = interval(delay);
         loop {
+            // external robots block while waiting for their answers, meanwhile the window can still add or remove robots
+            let robots_view = game_state.lock().await.clone();
+            let playing = robots.lock().await.playing(&robots_view);
+            let mut robot_moves = HashMap::new();
+            tokio::task::block_in_place(|| playing.steer(&robots_view, &config, &mut robot_moves));
+
+            // the turn starts from the state after the wait, so players who joined meanwhile are kept
             let mut moves_copy = moves.lock().await.clone();
+            moves_copy.extend(robot_moves);
             let mut state_copy = game_state.lock().await.clone();
+            // leaving players are applied to the copy, the state they left in is replaced by it
+            for player_id in liveness.take_left().await {
+                state_copy.zombify_player(player_id);
+            }
             moves_copy.retain(|id, direction| {
                state_copy.steer_validate(*direction, *id)
             });
             state_copy.update_snakes(&moves_copy, &config);
 
-            for player in state_copy.mut_players().mut_players() {
-                if !players_alive.lock().await.contains(&player.get_id()){
-                    if player.get_role() == VIEWER || player.get_id() == MY_ID.load(SeqCst){
+            let players = state_copy.get_players().get_players().to_vec();
+            for player in players {
+                if !liveness.is_alive(player.get_id()).await {
+                    if player.get_id() == MY_ID.load(SeqCst) || player.is_local() {
                         continue;
                     }
-                    player.set_role(VIEWER);
-                    println!("killed {}", player.get_id());
-                    println!("{:?}", player);
+                    // nothing came from the player for 0.8 of a turn, stop resending to it
+                    deliveries.declare_dead(player.get_id()).await;
+                    if player.get_role() == VIEWER {
+                        continue;
+                    }
+                    // like a player who left, its snake crawls on with nobody steering it
+                    state_copy.zombify_player(player.get_id());
                 }
             }
 
             //trying to make new deputy
             if state_copy.get_players().get_players().len() > 1 {
                 if state_copy.get_players().get_players().iter().find(|p| p.get_role() == DEPUTY).is_none() {
-                    if let Some(player) = state_copy.mut_players().mut_players().iter_mut().find(|p| p.get_role() == NORMAL) {
+                    // network robots can not take over the game, so they are never picked
+                    if let Some(player) = state_copy.mut_players().mut_players().iter_mut()
+                        .find(|p| p.get_role() == NORMAL && !p.is_local() && p.get_field_type() != PlayerType::ROBOT) {
                         player.set_role(DEPUTY);
                         let mut message = GameMessage_RoleChangeMsg::default();
                         message.set_receiver_role(DEPUTY);
@@ -777,20 +1082,22 @@ pub(crate) mod connection {
                             Some(player.get_id()),
                             player.get_ip_address().into(),
                             player.get_port(),
-                            awaiting_packages.clone(),
+                            deliveries.clone(),
                         ).await;
                     }
                 }
             }
 
-            players_alive.lock().await.clear();
+            // the master's own window and robots see the new turn right away
+            notifier.player_changes(&*game_state.lock().await, &state_copy, MY_ID.load(SeqCst));
+            game_state.lock().await.clone_from(&state_copy);
 
             let message = GameMessage_StateMsg::custom_new(state_copy.clone());
             send_to_all(
                 communication_socket.clone(),
                 GameMessage_oneof_Type::state(message),
                 state_copy.players.get_ref(),
-                awaiting_packages.clone()
+                deliveries.clone()
             ).await;
             interval.tick().await;
         }
@@ -809,4 +1116,41 @@ pub(crate) mod connection {
         }
     }
 
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn master_plays_turns_without_remote_players() {
+            let mut config = GameConfig::custom_default();
+            config.set_state_delay_ms(20);
+            let socket = UdpSocket::bind("127.0.0.1:0").await.expect("failed to create communication socket");
+            let addr = socket.local_addr().unwrap();
+            let mut state = GameState::new_custom("Alice".into(), addr.ip().to_string(), addr.port() as i32);
+            let mut robots = Robots::default();
+            robots.add(&mut state, Difficulty::Easy, &config);
+            MY_ID.store(1, SeqCst);
+            MASTER_ID.store(1, SeqCst);
+
+            let game_state = Arc::new(Mutex::new(state));
+            let liveness = Liveness::new(&config, game_state.lock().await.get_players().get_players());
+            let (notifier, _toasts) = notifications::channel();
+            let turns = tokio::spawn(game_turn_controller(
+                game_state.clone(),
+                Arc::new(Mutex::new(socket)),
+                Arc::new(Mutex::new(HashMap::new())),
+                Arc::new(Mutex::new(robots)),
+                Deliveries::new(&config),
+                config.clone(),
+                liveness,
+                notifier,
+            ));
+            // long after the master stopped hearing from itself
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            turns.abort();
+
+            assert!(game_state.lock().await.get_state_order() >= 5, "only {} turns played", game_state.lock().await.get_state_order());
+        }
+    }
+
 }
@@ -0,0 +1,107 @@
+use std::net::{IpAddr, SocketAddr};
+use protobuf::Message;
+use tokio::net::UdpSocket;
+use tokio::time::interval;
+
+use crate::bots::Difficulty;
+use crate::delivery::Deliveries;
+use crate::MAX_MESSAGE_SIZE;
+use crate::dto::*;
+use crate::dto::NodeRole::{MASTER, VIEWER};
+use crate::lobby::join_game;
+
+// Windowless robots joining a game over the network, one socket and task each
+pub async fn run_network_bots(name: String, levels: Vec<Difficulty>, game: GameAnnouncement, master_addr: SocketAddr, bind: IpAddr) {
+    let handles: Vec<_> = levels.into_iter().enumerate().map(|(index, difficulty)| {
+        tokio::spawn(run_network_bot(
+            format!("{} {} ({})", name, index + 1, difficulty.name()),
+            difficulty,
+            game.clone(),
+            master_addr,
+            bind,
+        ))
+    }).collect();
+
+    for handle in handles {
+        handle.await.expect("network bot failed");
+    }
+}
+
+async fn run_network_bot(name: String, difficulty: Difficulty, game: GameAnnouncement, mut master_addr: SocketAddr, bind: IpAddr) {
+    let socket = UdpSocket::bind(SocketAddr::new(bind, 0)).await.expect("failed to create communication socket");
+    let (my_id, mut master_id, mut game_state) = join_game(&socket, master_addr, name.clone(), game.get_game_name().into(), PlayerType::ROBOT).await;
+
+    let strategy = difficulty.strategy();
+    let mut msg_seq: i64 = 1; //zero was when we tried to connect
+    let mut buffer = vec![0; MAX_MESSAGE_SIZE];
+    let mut resend = interval(Deliveries::new(game.get_config()).resend_interval());
+    // the last steer the master did not ack yet, older ones are outdated by the time a new one is sent
+    let mut unacked: Option<(i64, Vec<u8>)> = None;
+
+    loop {
+        let (bytes, addr) = tokio::select! {
+            received = socket.recv_from(&mut buffer) => received.expect("failed to receive GameMessage"),
+            _ = resend.tick() => {
+                if let Some((_, steer)) = &unacked {
+                    let _ = socket.send_to(steer, master_addr).await;
+                }
+                continue;
+            },
+        };
+        let game_message = match GameMessage::parse_from_bytes(&buffer[..bytes]) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+
+        match game_message.Type.clone() {
+            Some(GameMessage_oneof_Type::state(converted)) => {
+                send_ack(&socket, &game_message, my_id, addr).await;
+                if converted.get_state().get_state_order() <= game_state.get_state_order() {
+                    continue;
+                }
+                game_state = converted.get_state().clone();
+
+                if game_state.get_players().get_players().iter().find(|p| p.get_id() == my_id).is_none_or(|p| p.get_role() == VIEWER) {
+                    return;
+                }
+
+                if let Some(dir) = strategy.choose_direction(&game_state, game.get_config(), my_id) {
+                    let steer = GameMessage::custom_new(
+                        msg_seq,
+                        Some(my_id),
+                        Some(master_id),
+                        GameMessage_oneof_Type::steer(GameMessage_SteerMsg::custom_new(dir)),
+                    );
+                    msg_seq += 1;
+                    let bytes = steer.write_to_bytes().expect("failed to serialize steer message");
+                    let _ = socket.send_to(&bytes, master_addr).await;
+                    unacked = Some((steer.get_msg_seq(), bytes));
+                }
+            },
+            Some(GameMessage_oneof_Type::role_change(converted)) => {
+                send_ack(&socket, &game_message, my_id, addr).await;
+                if converted.get_sender_role() == MASTER {
+                    master_id = game_message.get_sender_id();
+                    master_addr = addr;
+                }
+            },
+            Some(GameMessage_oneof_Type::ack(_)) if unacked.as_ref().is_some_and(|(seq, _)| *seq == game_message.get_msg_seq()) => {
+                unacked = None;
+            },
+            Some(GameMessage_oneof_Type::ping(_)) | Some(GameMessage_oneof_Type::error(_)) => {
+                send_ack(&socket, &game_message, my_id, addr).await;
+            },
+            _ => {}
+        }
+    }
+}
+
+async fn send_ack(socket: &UdpSocket, game_message: &GameMessage, my_id: i32, addr: SocketAddr) {
+    let ack = GameMessage::custom_new(
+        game_message.get_msg_seq(),
+        Some(my_id),
+        Some(game_message.get_sender_id()),
+        GameMessage_oneof_Type::ack(GameMessage_AckMsg::default()),
+    );
+    let _ = socket.send_to(&ack.write_to_bytes().expect("failed to serialize ack message"), addr).await;
+}
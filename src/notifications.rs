@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use piston_window::types::Color;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::drawing::Renderer;
+use crate::dto::{GameState, NodeRole};
+use crate::dto::NodeRole::{DEPUTY, MASTER, VIEWER};
+
+const SHOWN_FOR: Duration = Duration::from_secs(4);
+const FADE_OUT: Duration = Duration::from_millis(500);
+const MAX_SHOWN: usize = 4;
+const TOAST_WIDTH: f64 = 12.0;
+const TOAST_LENGTH: usize = 40;
+const ERROR_COLOR: Color = [1.0, 0.45, 0.45, 1.0];
+const INFO_COLOR: Color = [1.0, 1.0, 1.0, 1.0];
+
+#[derive(Clone, Debug)]
+pub enum Notice {
+    Error(String),
+    Info(String),
+}
+
+// Handed to the networking tasks, sending never blocks and nothing breaks when no window listens
+#[derive(Clone)]
+pub struct Notifier {
+    sender: UnboundedSender<Notice>,
+}
+
+impl Notifier {
+    pub fn error(&self, text: impl Into<String>) {
+        let _ = self.sender.send(Notice::Error(text.into()));
+    }
+
+    pub fn info(&self, text: impl Into<String>) {
+        let _ = self.sender.send(Notice::Info(text.into()));
+    }
+
+    // Reports who joined, left or got another role between two states
+    pub fn player_changes(&self, before: &GameState, after: &GameState, my_id: i32) {
+        let name = |id: i32, fallback: &str| if id == my_id { "You".to_string() } else { fallback.to_string() };
+        for player in after.get_players().get_players() {
+            let who = name(player.get_id(), player.get_name());
+            let Some(old) = before.get_players().get_players().iter().find(|p| p.get_id() == player.get_id()) else {
+                self.info(format!("{} joined", who));
+                continue;
+            };
+            if old.get_role() == player.get_role() {
+                continue;
+            }
+            let has_snake = after.get_snakes().iter().any(|s| s.get_player_id() == player.get_id());
+            match player.get_role() {
+                // a snake without a player is left behind by someone who disconnected
+                VIEWER if has_snake => self.info(format!("{} left", who)),
+                VIEWER => self.info(format!("{} {} watching", who, verb(&who))),
+                role if old.get_role() == VIEWER => self.info(format!("{} {} back as {}", who, verb(&who), role_name(role))),
+                role => self.info(format!("{} {} the {} now", who, verb(&who), role_name(role))),
+            }
+        }
+        for player in before.get_players().get_players() {
+            if !after.get_players().get_players().iter().any(|p| p.get_id() == player.get_id()) {
+                self.info(format!("{} left", name(player.get_id(), player.get_name())));
+            }
+        }
+    }
+}
+
+fn verb(who: &str) -> &'static str {
+    if who == "You" { "are" } else { "is" }
+}
+
+fn role_name(role: NodeRole) -> &'static str {
+    match role {
+        MASTER => "master",
+        DEPUTY => "deputy",
+        VIEWER => "viewer",
+        _ => "player",
+    }
+}
+
+pub fn channel() -> (Notifier, Toasts) {
+    let (sender, receiver) = unbounded_channel();
+    (Notifier { sender }, Toasts { receiver, shown: VecDeque::new() })
+}
+
+// Notices shown in the corner of the window for a few seconds each
+pub struct Toasts {
+    receiver: UnboundedReceiver<Notice>,
+    shown: VecDeque<(Notice, Instant)>,
+}
+
+impl Toasts {
+    // For notices of the window itself, they do not need to go through a Notifier
+    pub fn show(&mut self, notice: Notice) {
+        self.shown.push_back((notice, Instant::now()));
+    }
+
+    pub fn update(&mut self) {
+        while let Ok(notice) = self.receiver.try_recv() {
+            self.shown.push_back((notice, Instant::now()));
+        }
+        self.shown.retain(|(_, since)| since.elapsed() < SHOWN_FOR);
+        while self.shown.len() > MAX_SHOWN {
+            self.shown.pop_front();
+        }
+    }
+
+    // The notices still shown, oldest first
+    pub fn shown(&self) -> impl Iterator<Item = &Notice> {
+        self.shown.iter().map(|(notice, _)| notice)
+    }
+
+    pub fn draw(&self, r: &mut impl Renderer, width: f64) {
+        let x = width - TOAST_WIDTH - 1.0;
+        for (row, (notice, since)) in self.shown.iter().enumerate() {
+            let left = SHOWN_FOR.saturating_sub(since.elapsed());
+            let alpha = (left.as_secs_f32() / FADE_OUT.as_secs_f32()).min(1.0);
+            let (mut color, text) = match notice {
+                Notice::Error(text) => (ERROR_COLOR, text),
+                Notice::Info(text) => (INFO_COLOR, text),
+            };
+            color[3] = alpha;
+
+            let text = if text.chars().count() > TOAST_LENGTH {
+                format!("{}…", text.chars().take(TOAST_LENGTH - 1).collect::<String>())
+            } else {
+                text.clone()
+            };
+
+            let y = 1.0 + row as f64 * 1.4;
+            r.fill_rect([0.0, 0.0, 0.0, 0.6 * alpha], x, y, TOAST_WIDTH, 1.2);
+            r.draw_text(color, 12, &text, x + 0.3, y + 0.85);
+        }
+    }
+}
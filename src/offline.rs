@@ -0,0 +1,112 @@
+use std::path::Path;
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use piston_window::*;
+
+use crate::bots::{Difficulty, Robots};
+use crate::controls::KeyBindings;
+use crate::dto::{Direction, GameConfig, GameState, NodeRole};
+use crate::notifications;
+use crate::window::{run_window, Session};
+
+// Plays the whole game inside the window: no sockets, the turns are driven by a local timer
+pub async fn run_offline(
+    window: PistonWindow,
+    game_state: GameState,
+    config: &GameConfig,
+    font: Option<&Path>,
+    bindings: KeyBindings,
+    seats: Vec<(i32, KeyBindings)>,
+    robots: Robots,
+) {
+    let session = OfflineSession {
+        my_id: game_state.get_players().get_players()[0].get_id(),
+        game_state,
+        config: config.clone(),
+        seats,
+        robots,
+        moves: HashMap::new(),
+        thinking: None,
+        delay: Duration::from_millis(config.get_state_delay_ms() as u64),
+        last_turn: Instant::now(),
+    };
+    // nothing but the window itself has notices to show without networking
+    let (_, toasts) = notifications::channel();
+    run_window(window, session, config, bindings, font, toasts).await;
+}
+
+struct OfflineSession {
+    my_id: i32,
+    game_state: GameState,
+    config: GameConfig,
+    seats: Vec<(i32, KeyBindings)>,
+    robots: Robots,
+    moves: HashMap<i32, Direction>,
+    // the robots choose their moves for the next turn next to the window, external ones may take long to answer
+    thinking: Option<JoinHandle<HashMap<i32, Direction>>>,
+    delay: Duration,
+    last_turn: Instant,
+}
+
+impl Session for OfflineSession {
+    fn my_id(&self) -> i32 {
+        self.my_id
+    }
+
+    async fn state(&mut self) -> GameState {
+        if self.last_turn.elapsed() >= self.delay {
+            self.last_turn = Instant::now();
+            // robots still thinking keep their directions for this turn
+            if self.thinking.as_ref().is_some_and(|thinking| thinking.is_finished()) {
+                let robot_moves = self.thinking.take().unwrap().join().expect("a robot strategy panicked");
+                self.moves.extend(robot_moves);
+            }
+            let game_state = &self.game_state;
+            self.moves.retain(|id, direction| game_state.steer_validate(*direction, *id));
+            self.game_state.update_snakes(&self.moves, &self.config);
+
+            if self.thinking.is_none() {
+                let playing = self.robots.playing(&self.game_state);
+                let (state, config) = (self.game_state.clone(), self.config.clone());
+                self.thinking = Some(thread::spawn(move || {
+                    let mut robot_moves = HashMap::new();
+                    playing.steer(&state, &config, &mut robot_moves);
+                    robot_moves
+                }));
+            }
+        }
+        self.game_state.clone()
+    }
+
+    async fn steer_seats(&mut self, key: Key) {
+        for (player_id, seat) in &self.seats {
+            if let Some(dir) = seat.action(key).and_then(|a| a.direction()) {
+                self.moves.insert(*player_id, dir);
+            }
+        }
+    }
+
+    async fn steer(&mut self, dir: Direction) {
+        self.moves.insert(self.my_id, dir);
+    }
+
+    async fn add_robot(&mut self, difficulty: Difficulty) {
+        self.robots.add(&mut self.game_state, difficulty, &self.config);
+    }
+
+    async fn remove_robot(&mut self) {
+        self.robots.remove(&mut self.game_state);
+    }
+
+    fn can_rejoin(&self) -> bool {
+        true
+    }
+
+    async fn rejoin(&mut self) {
+        self.game_state.revive_player(self.my_id, NodeRole::MASTER, &self.config);
+    }
+
+    // nobody else has to be told
+    async fn leave(&mut self) {}
+}
@@ -0,0 +1,54 @@
+extern crate serde;
+
+use std::sync::OnceLock;
+use piston_window::types::Color;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Default,
+    ColorBlind,
+}
+
+// bright enough for the dark background and far from the orange food and the grey border
+const DEFAULT_PALETTE: [Color; 9] = [
+    [0.94, 0.89, 0.26, 1.0], // yellow
+    [0.31, 0.76, 0.97, 1.0], // light blue
+    [0.88, 0.25, 0.98, 1.0], // magenta
+    [0.40, 0.73, 0.42, 1.0], // green
+    [1.0, 0.32, 0.32, 1.0],  // red
+    [0.70, 0.53, 1.0, 1.0],  // lavender
+    [0.11, 0.91, 0.71, 1.0], // teal
+    [1.0, 0.50, 0.67, 1.0],  // pink
+    [0.78, 1.0, 0.0, 1.0],   // lime
+];
+
+// Okabe-Ito colours, which stay apart for every common colour vision deficiency, followed by lighter tints of them.
+// Orange and vermillion are left out as they look like the food, blue and black are too dark for the background.
+const COLOR_BLIND_PALETTE: [Color; 8] = [
+    [0.34, 0.71, 0.91, 1.0], // sky blue
+    [0.94, 0.89, 0.26, 1.0], // yellow
+    [0.0, 0.62, 0.45, 1.0],  // bluish green
+    [0.80, 0.47, 0.65, 1.0], // reddish purple
+    [0.72, 0.88, 0.97, 1.0], // pale sky blue
+    [0.98, 0.96, 0.72, 1.0], // pale yellow
+    [0.55, 0.85, 0.73, 1.0], // pale bluish green
+    [0.93, 0.78, 0.87, 1.0], // pale reddish purple
+];
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+pub fn set_theme(theme: Theme) {
+    THEME.set(theme).expect("the theme is set once at startup");
+}
+
+// Colour of the player at `index` in the player list, the palette starts over when it runs out
+pub fn player_color(index: usize) -> Color {
+    let palette: &[Color] = match THEME.get().copied().unwrap_or_default() {
+        Theme::Default => &DEFAULT_PALETTE,
+        Theme::ColorBlind => &COLOR_BLIND_PALETTE,
+    };
+    palette[index % palette.len()]
+}
@@ -0,0 +1,179 @@
+extern crate serde;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::Renderer;
+use crate::dto::GameState;
+
+const RECORDS_FILE: &str = "records.json";
+const HIGH_SCORES_SHOWN: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameRecord {
+    pub player_name: String,
+    pub game_name: String,
+    pub score: i32,
+    pub placement: usize,
+    pub length: i32,
+    pub duration_secs: u64,
+    pub date: String,
+}
+
+pub struct RecordStore {
+    path: PathBuf,
+    records: Vec<GameRecord>,
+    // the file could not be read and was left where it is, saving would wipe the results in it
+    read_only: bool,
+}
+
+impl RecordStore {
+    pub fn data_dir() -> PathBuf {
+        dirs::data_dir().unwrap_or_else(|| PathBuf::from(".")).join("rust-snake")
+    }
+
+    // A missing file means there is no history yet. A broken one is moved aside and reported,
+    // so the results in it are not overwritten by the next save.
+    pub fn load() -> (RecordStore, Option<String>) {
+        let path = RecordStore::data_dir().join(RECORDS_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return (RecordStore { path, records: Vec::new(), read_only: false }, None),
+            Err(e) => {
+                let error = format!("failed to read game records from {}: {}, new results are not saved", path.display(), e);
+                return (RecordStore { path, records: Vec::new(), read_only: true }, Some(error));
+            },
+        };
+        match serde_json::from_str(&content) {
+            Ok(records) => (RecordStore { path, records, read_only: false }, None),
+            Err(e) => {
+                let backup = path.with_extension("json.broken");
+                let (read_only, error) = match fs::rename(&path, &backup) {
+                    Ok(()) => (false, format!("game records in {} are broken ({}), they were moved to {}", path.display(), e, backup.display())),
+                    Err(_) => (true, format!("game records in {} are broken ({}), new results are not saved", path.display(), e)),
+                };
+                (RecordStore { path, records: Vec::new(), read_only }, Some(error))
+            },
+        }
+    }
+
+    pub fn save(&self) {
+        if self.read_only {
+            eprintln!("game records are not saved to {}, it could not be read", self.path.display());
+            return;
+        }
+        if let Some(dir) = self.path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("failed to create {}: {}", dir.display(), e);
+                return;
+            }
+        }
+        let content = serde_json::to_string_pretty(&self.records).expect("failed to serialize game records");
+        if let Err(e) = fs::write(&self.path, content) {
+            eprintln!("failed to save game records to {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn append(&mut self, records: Vec<GameRecord>) {
+        self.records.extend(records);
+    }
+
+    pub fn best(&self, count: usize) -> Vec<&GameRecord> {
+        let mut best: Vec<&GameRecord> = self.records.iter().collect();
+        best.sort_by(|a, b| b.score.cmp(&a.score).then(b.length.cmp(&a.length)));
+        best.truncate(count);
+        best
+    }
+
+    // Covers the given area (in cells) with the list of the best results
+    pub fn draw(&self, r: &mut impl Renderer, width: f64, height: f64) {
+        r.fill_rect([0.0, 0.0, 0.0, 0.85], 0.0, 0.0, width, height);
+
+        let text_color = [1.0, 1.0, 1.0, 1.0];
+        let font_size = 15;
+
+        r.draw_text(text_color, 20, "High scores", 1.6, 1.6);
+
+        let mut y_position = 3.2;
+        for (index, record) in self.best(HIGH_SCORES_SHOWN).iter().enumerate() {
+            r.draw_text(
+                text_color,
+                font_size,
+                &format!("{}. {} - {} (#{} in {}, length {}, {}s, {})",
+                         index + 1, record.player_name, record.score, record.placement,
+                         record.game_name, record.length, record.duration_secs, record.date),
+                1.6,
+                y_position,
+            );
+            y_position += 1.0;
+        }
+    }
+}
+
+struct PlayerProgress {
+    length: i32,
+    // since the tracker started until the snake was last seen
+    played: Duration,
+    // when the current snake appeared, None while the player has no snake
+    life_started: Option<Instant>,
+    survived: Duration,
+}
+
+// Follows the states received during one match to know what the final results looked like
+pub struct MatchTracker {
+    game_name: String,
+    started: Instant,
+    players: HashMap<i32, PlayerProgress>,
+}
+
+impl MatchTracker {
+    pub fn new(game_name: String) -> MatchTracker {
+        MatchTracker { game_name, started: Instant::now(), players: HashMap::new() }
+    }
+
+    pub fn observe(&mut self, state: &GameState) {
+        for player in state.get_players().get_players() {
+            let progress = self.players.entry(player.get_id())
+                .or_insert(PlayerProgress { length: 0, played: Duration::ZERO, life_started: None, survived: Duration::ZERO });
+            // dead snakes are removed from the state, so the last seen values stay
+            if let Some(snake) = state.get_snakes().iter().find(|s| s.get_player_id() == player.get_id()) {
+                // a rejoined player starts a new life
+                let life_started = *progress.life_started.get_or_insert_with(Instant::now);
+                progress.length = snake.get_length();
+                progress.played = self.started.elapsed();
+                progress.survived = life_started.elapsed();
+            } else {
+                progress.life_started = None;
+            }
+        }
+    }
+
+    // How long the player's last snake lived
+    pub fn survived(&self, player_id: i32) -> Duration {
+        self.players.get(&player_id).map_or(Duration::ZERO, |p| p.survived)
+    }
+
+    pub fn finish(&self, state: &GameState) -> Vec<GameRecord> {
+        let date = Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let mut players: Vec<_> = state.get_players().get_players().iter().collect();
+        players.sort_by_key(|p| std::cmp::Reverse(p.get_score()));
+
+        players.iter().enumerate().map(|(index, player)| {
+            let progress = self.players.get(&player.get_id());
+            GameRecord {
+                player_name: player.get_name().to_string(),
+                game_name: self.game_name.clone(),
+                score: player.get_score(),
+                placement: index + 1,
+                length: progress.map_or(0, |p| p.length),
+                duration_secs: progress.map_or(0, |p| p.played.as_secs()),
+                date: date.clone(),
+            }
+        }).collect()
+    }
+}
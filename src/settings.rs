@@ -0,0 +1,37 @@
+extern crate serde;
+
+use std::path::PathBuf;
+use config::{Config, File};
+use serde::Deserialize;
+
+use crate::controls::ControlsSettings;
+use crate::palette::Theme;
+
+const SETTINGS_FILE: &str = "settings.toml";
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub controls: ControlsSettings,
+    // a .ttf file used instead of the bundled font
+    pub font: Option<PathBuf>,
+    pub theme: Theme,
+}
+
+impl Settings {
+    pub fn path() -> PathBuf {
+        dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("rust-snake").join(SETTINGS_FILE)
+    }
+
+    pub fn load() -> Settings {
+        let settings = Config::builder()
+            .add_source(File::from(Settings::path()).required(false))
+            .build()
+            .and_then(|c| c.try_deserialize());
+
+        settings.unwrap_or_else(|e| {
+            eprintln!("failed to load settings, using defaults: {}", e);
+            Settings::default()
+        })
+    }
+}
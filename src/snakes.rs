@@ -1,13 +1,23 @@
 extern crate serde;
 
 use std::cmp::{max, min};
-use piston_window::Context;
-use piston_window::G2d;
-use rand::{Rng, SeedableRng};
+use piston_window::types::Color;
 
-use crate::drawing::draw_block;
+use crate::drawing::Renderer;
 use crate::dto::{Direction, GameConfig, GameState_Coord, GameState_Snake};
-use crate::dto::GameState_Snake_SnakeState::ALIVE;
+use crate::dto::GameState_Snake_SnakeState::{ALIVE, ZOMBIE};
+
+const TAIL_DARKENING: f32 = 0.4;
+const EYE_SIZE: f64 = 0.2;
+const EYE_COLOR: Color = [0.1, 0.1, 0.1, 1.0];
+
+// How the parts of a snake look in one frame
+struct DrawStyle {
+    // already greyed for zombies and faded for snakes that just died
+    color: Color,
+    // the end of the tail on its way to the last cell, the tail fills the last cell when there is none
+    sliding_tail: Option<(f64, f64)>,
+}
 
 impl GameState_Coord {
     pub fn new_custom(x:i32, y:i32) -> Self {
@@ -106,34 +116,88 @@ impl GameState_Snake {
         snake
     }
 
-    pub fn draw(&self, con: &Context, g: &mut G2d, game_config: &GameConfig) {
-        let mut rng = rand::rngs::StdRng::seed_from_u64((self.get_player_id() as i64 + i32::MAX as i64) as u64);
-        let color = generate_random_color(&mut rng);
-
-        let mut cur = self.get_head_position().clone();
-        draw_block(color, cur.get_x(), cur.get_y(), con, g);
+    pub fn draw(&self, color: Color, r: &mut impl Renderer, game_config: &GameConfig) {
+        let (width, height) = (game_config.get_width(), game_config.get_height());
+        let cells = self.get_cells(width, height);
+        let head = (cells[0].get_x() as f64, cells[0].get_y() as f64);
+        self.draw_parts(r, DrawStyle { color: self.shown_color(color), sliding_tail: None }, &cells, head, game_config);
+    }
 
-        //todo оптимизировать особые клетки (после движения головы)
-        //todo переход через границу нормальный
-        for coord in self.get_points().iter().skip(1) {
-            if cur.check_next_for_jump(&coord, game_config.get_width(), game_config.get_height()) {
-                cur = cur.next(coord, game_config.get_width(), game_config.get_height());
-                continue;
-            }
+    // Draws the snake on its way from the previous state, progress goes from 0 to 1 during the turn
+    pub fn draw_interpolated(&self, previous: &GameState_Snake, progress: f64, color: Color, r: &mut impl Renderer, game_config: &GameConfig) {
+        let (width, height) = (game_config.get_width(), game_config.get_height());
+        let cells = self.get_cells(width, height);
+        let previous_cells = previous.get_cells(width, height);
+        let previous_tail = &previous_cells[previous_cells.len() - 1];
+
+        let head_step = wrapped_step(&previous_cells[0], &cells[0], width, height);
+        let tail_step = wrapped_step(previous_tail, &cells[cells.len() - 1], width, height);
+        let (Some(_), Some((dx, dy))) = (head_step, tail_step) else {
+            // moved more than a cell, nothing to interpolate
+            self.draw(color, r, game_config);
+            return;
+        };
+
+        let head = self.interpolated_head(previous, progress, width, height);
+        let tail = (previous_tail.get_x() as f64 + dx * progress, previous_tail.get_y() as f64 + dy * progress);
+        self.draw_parts(r, DrawStyle { color: self.shown_color(color), sliding_tail: Some(tail) }, &cells, head, game_config);
+    }
 
-            assert!((coord.get_x() == 0) || (coord.get_y() == 0), "impossible coord given");
+    // A snake that died this turn fades out until the next state comes
+    pub fn draw_dead(&self, progress: f64, color: Color, r: &mut impl Renderer, game_config: &GameConfig) {
+        let (width, height) = (game_config.get_width(), game_config.get_height());
+        let cells = self.get_cells(width, height);
+        let head = (cells[0].get_x() as f64, cells[0].get_y() as f64);
+        let mut color = greyed(color);
+        color[3] = (1.0 - progress) as f32;
+        self.draw_parts(r, DrawStyle { color, sliding_tail: None }, &cells, head, game_config);
+    }
 
-            let range = min(cur.get_x(),cur.get_x() + coord.get_x())..=max(cur.get_x(),cur.get_x() + coord.get_x());
+    // Zombies have nobody steering them, so they lose their colour
+    fn shown_color(&self, color: Color) -> Color {
+        if self.get_state() == ZOMBIE { greyed(color) } else { color }
+    }
 
-            for x in range {
-                draw_block(color, x, cur.get_y(), con, g);
+    // The body gets darker towards a narrow tail, the head has eyes looking where the snake goes
+    fn draw_parts(&self, r: &mut impl Renderer, style: DrawStyle, cells: &[GameState_Coord], head: (f64, f64), game_config: &GameConfig) {
+        let DrawStyle { color, sliding_tail } = style;
+        let (width, height) = (game_config.get_width(), game_config.get_height());
+        let last = cells.len() - 1;
+        let shade = |index: usize| {
+            let factor = 1.0 - TAIL_DARKENING * index as f32 / last.max(1) as f32;
+            [color[0] * factor, color[1] * factor, color[2] * factor, color[3]]
+        };
+        // narrow across the direction the tail points to
+        let vertical_tail = last > 0 && wrapped_step(&cells[last], &cells[last - 1], width, height).is_some_and(|(dx, _)| dx == 0.0);
+        let taper = |x: f64, y: f64| if vertical_tail { [x + 0.2, y, 0.6, 1.0] } else { [x, y + 0.2, 1.0, 0.6] };
+
+        for (index, cell) in cells.iter().enumerate().skip(1) {
+            if index < last {
+                r.fill_cell(shade(index), cell.get_x(), cell.get_y());
+            } else {
+                fill_wrapped(r, shade(index), taper(cell.get_x() as f64, cell.get_y() as f64), width, height);
             }
-            let range = min(cur.get_y(), cur.get_y() + coord.get_y())..=max(cur.get_y(), cur.get_y() + coord.get_y());
+        }
+        if let (Some((x, y)), true) = (sliding_tail, last > 0) {
+            fill_wrapped(r, shade(last), taper(x, y), width, height);
+        }
 
-            for y in range {
-                draw_block(color, cur.get_x(), y, con, g);
-            }
-            cur = cur.next(coord, game_config.get_width(), game_config.get_height());
+        fill_wrapped(r, color, [head.0, head.1, 1.0, 1.0], width, height);
+        let delta = self.get_head_direction().get_delta();
+        let (dx, dy) = (delta.get_x() as f64, delta.get_y() as f64);
+        for side in [-1.0, 1.0] {
+            let eye_x = head.0 + 0.5 + 0.2 * dx - 0.22 * dy * side - EYE_SIZE / 2.0;
+            let eye_y = head.1 + 0.5 + 0.2 * dy + 0.22 * dx * side - EYE_SIZE / 2.0;
+            fill_wrapped(r, [EYE_COLOR[0], EYE_COLOR[1], EYE_COLOR[2], color[3]], [eye_x, eye_y, EYE_SIZE, EYE_SIZE], width, height);
+        }
+    }
+
+    // Position of the head on its way from the previous state
+    pub fn interpolated_head(&self, previous: &GameState_Snake, progress: f64, width: i32, height: i32) -> (f64, f64) {
+        let (from, to) = (previous.get_head_position(), self.get_head_position());
+        match wrapped_step(from, to, width, height) {
+            Some((dx, dy)) => (from.get_x() as f64 + dx * progress, from.get_y() as f64 + dy * progress),
+            None => (to.get_x() as f64, to.get_y() as f64),
         }
     }
 
@@ -202,6 +266,24 @@ impl GameState_Snake {
         self.get_points().get(0).unwrap()
     }
 
+    pub fn get_length(&self) -> i32 {
+        1 + self.get_points().iter().skip(1).map(|c| c.get_x().abs() + c.get_y().abs()).sum::<i32>()
+    }
+
+    // Every cell of the snake from head to tail, following the wraps through the border
+    pub fn get_cells(&self, width: i32, height: i32) -> Vec<GameState_Coord> {
+        let mut cur = self.get_head_position().clone();
+        let mut cells = vec![cur.clone()];
+        for coord in self.get_points().iter().skip(1) {
+            let step = GameState_Coord::new_custom(coord.get_x().signum(), coord.get_y().signum());
+            for _ in 0..(coord.get_x().abs() + coord.get_y().abs()) {
+                cur = cur.next(&step, width, height);
+                cells.push(cur.clone());
+            }
+        }
+        cells
+    }
+
     pub fn get_tail_position(&self, width: i32, height:i32) -> GameState_Coord {
         let mut cur = self.get_head_position().clone();
         for coord in self.get_points().iter().skip(1) {
@@ -281,14 +363,43 @@ impl GameState_Snake {
     }
 }
 
-fn generate_random_color(rng: &mut impl Rng) -> [f32; 4] {
-    // Generate random RGB values between 0.0 and 1.0
-    let red = rng.gen_range(0.0..1.0);
-    let green = rng.gen_range(0.0..1.0);
-    let blue = rng.gen_range(0.0..1.0);
+// Step between two neighbouring cells, going through the border when that is shorter
+fn wrapped_step(from: &GameState_Coord, to: &GameState_Coord, width: i32, height: i32) -> Option<(f64, f64)> {
+    // the border takes one cell on each side
+    let wrap = |delta: i32, size: i32| {
+        let inner = size - 2;
+        if delta > inner / 2 { delta - inner } else if delta < -inner / 2 { delta + inner } else { delta }
+    };
+    let dx = wrap(to.get_x() - from.get_x(), width);
+    let dy = wrap(to.get_y() - from.get_y(), height);
+    if dx.abs() + dy.abs() > 1 {
+        return None;
+    }
+    Some((dx as f64, dy as f64))
+}
 
-    // Set alpha to 1.0 (fully opaque)
-    let alpha = 1.0;
+// Fills a rectangle that may stick out of the field, the part outside comes in from the other side
+fn fill_wrapped(r: &mut impl Renderer, color: Color, rect: [f64; 4], width: i32, height: i32) {
+    let [x, y, rect_width, rect_height] = rect;
+    let copies = |position: f64, size: i32| {
+        let inner = (size - 2) as f64;
+        if position < 1.0 {
+            vec![position, position + inner]
+        } else if position > inner {
+            vec![position, position - inner]
+        } else {
+            vec![position]
+        }
+    };
+    for x in copies(x, width) {
+        for y in copies(y, height) {
+            r.fill_rect(color, x, y, rect_width, rect_height);
+        }
+    }
+}
 
-    [red, green, blue, alpha]
-}
\ No newline at end of file
+fn greyed(color: Color) -> Color {
+    let luminance = 0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2];
+    let grey = 0.25 + 0.4 * luminance;
+    [grey, grey, grey, color[3]]
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use image::RgbaImage;
+use piston_window::types::Color;
+use protobuf::{CodedInputStream, Message};
+
+use crate::BACK_COLOR;
+use crate::drawing::{Renderer, BLOCK_SIZE};
+use crate::dto::{GameConfig, GameState};
+use crate::records::RecordStore;
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+// Rasterizes the game on the CPU, so pictures can be taken without a window
+pub struct PixelRenderer {
+    image: RgbaImage,
+}
+
+impl PixelRenderer {
+    pub fn new(config: &GameConfig) -> Self {
+        let (width, height) = (config.get_width() as f64 * BLOCK_SIZE, config.get_height() as f64 * BLOCK_SIZE);
+        let mut renderer = PixelRenderer { image: RgbaImage::new(width as u32, height as u32) };
+        renderer.fill_rect(BACK_COLOR, 0.0, 0.0, config.get_width() as f64, config.get_height() as f64);
+        renderer
+    }
+
+    fn blend(&mut self, color: Color, x: u32, y: u32) {
+        let pixel = self.image.get_pixel_mut(x, y);
+        let alpha = color[3];
+        for channel in 0..3 {
+            let old = pixel[channel] as f32 / 255.0;
+            pixel[channel] = ((color[channel] * alpha + old * (1.0 - alpha)) * 255.0).round() as u8;
+        }
+        pixel[3] = 255;
+    }
+}
+
+impl Renderer for PixelRenderer {
+    fn fill_cell(&mut self, color: Color, x: i32, y: i32) {
+        self.fill_rect(color, x as f64, y as f64, 1.0, 1.0);
+    }
+
+    fn fill_rect(&mut self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        let to_pixel = |cells: f64, limit: u32| ((cells * BLOCK_SIZE).round().max(0.0) as u32).min(limit);
+        let (image_width, image_height) = self.image.dimensions();
+        for py in to_pixel(y, image_height)..to_pixel(y + height, image_height) {
+            for px in to_pixel(x, image_width)..to_pixel(x + width, image_width) {
+                self.blend(color, px, py);
+            }
+        }
+    }
+
+    // there is no font on the CPU side, snapshots only show the board
+    fn draw_text(&mut self, _color: Color, _size: u32, _text: &str, _x: f64, _y: f64) {}
+}
+
+pub fn render_png(state: &GameState, config: &GameConfig, path: &Path) -> Result<(), String> {
+    let mut renderer = PixelRenderer::new(config);
+    state.draw(&mut renderer, config);
+    renderer.image.save(path).map_err(|e| format!("failed to save the snapshot {}: {}", path.display(), e))
+}
+
+// A recorded state is the config followed by the state, both length delimited
+pub fn save_state(state: &GameState, config: &GameConfig, path: &Path) -> Result<(), String> {
+    let mut bytes = config.write_length_delimited_to_bytes().expect("failed to serialize the config");
+    bytes.extend(state.write_length_delimited_to_bytes().expect("failed to serialize the state"));
+    fs::write(path, bytes).map_err(|e| format!("failed to save the recorded state {}: {}", path.display(), e))
+}
+
+pub fn load_state(path: &Path) -> Result<(GameConfig, GameState), String> {
+    let bytes = fs::read(path).map_err(|e| format!("failed to read the recorded state {}: {}", path.display(), e))?;
+    let mut input = CodedInputStream::from_bytes(&bytes);
+    let config: GameConfig = input.read_message().map_err(|e| format!("failed to deserialize the recorded config {}: {}", path.display(), e))?;
+    let state: GameState = input.read_message().map_err(|e| format!("failed to deserialize the recorded state {}: {}", path.display(), e))?;
+    Ok((config, state))
+}
+
+// Keeps both the picture and the state it was made of, returns the path of the picture
+pub fn take_snapshot(state: &GameState, config: &GameConfig) -> Result<PathBuf, String> {
+    let dir = RecordStore::data_dir().join(SNAPSHOTS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("failed to create the snapshots directory {}: {}", dir.display(), e))?;
+
+    let base = dir.join(format!("snapshot-{}-{}", Local::now().format("%Y%m%d-%H%M%S%3f"), state.get_state_order()));
+    save_state(state, config, &base.with_extension("state"))?;
+    let png = base.with_extension("png");
+    render_png(state, config, &png)?;
+    Ok(png)
+}
+
+pub fn render_recorded(state_path: &Path) -> Result<PathBuf, String> {
+    let (config, state) = load_state(state_path)?;
+    let png = state_path.with_extension("png");
+    render_png(&state, &config, &png)?;
+    Ok(png)
+}
@@ -0,0 +1,189 @@
+use std::io::{stdout, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use crossterm::{cursor, execute, queue, terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color as TermColor, Print, ResetColor, SetForegroundColor, Stylize};
+use piston_window::Key;
+use piston_window::types::Color;
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::interval;
+
+use crate::connection::{key_handler, leave};
+use crate::controls::{Action, KeyBindings};
+use crate::delivery::Deliveries;
+use crate::dto::{GameConfig, GameState};
+use crate::drawing::{DrawCall, RecordingRenderer};
+use crate::notifications::{Notice, Toasts};
+use crate::records::{MatchTracker, RecordStore};
+use crate::snapshot::take_snapshot;
+
+// Same as the piston client, but draws the board with ANSI colours in the terminal
+pub async fn terminal_loop(
+    socket: Arc<Mutex<UdpSocket>>,
+    game_state: Arc<Mutex<GameState>>,
+    deliveries: Deliveries,
+    config: &GameConfig,
+    bindings: KeyBindings,
+    mut toasts: Toasts,
+    my_id: i32,
+) {
+    let terminal_guard = TerminalGuard::enter();
+
+    // crossterm blocks while waiting for input, so it gets a thread of its own
+    let (keys_sender, mut keys) = mpsc::unbounded_channel();
+    std::thread::spawn(move || loop {
+        if let Ok(Event::Key(key_event)) = event::read() {
+            if key_event.kind == KeyEventKind::Press && keys_sender.send(key_event.code).is_err() {
+                return;
+            }
+        }
+    });
+
+    let (mut record_store, records_error) = RecordStore::load();
+    let mut tracker = MatchTracker::new(game_state.lock().await.generate_announcement(config.clone()).get_game_name().to_string());
+    let mut redraw = interval(Duration::from_millis(50));
+    // the terminal is in raw mode, so messages are shown below the players instead of printed
+    let mut status: Option<String> = records_error;
+
+    loop {
+        tokio::select! {
+            Some(code) = keys.recv() => {
+                match to_piston_key(code).and_then(|key| bindings.action(key)) {
+                    Some(Action::Leave) => break,
+                    Some(Action::Screenshot) => {
+                        let state = game_state.lock().await.clone();
+                        status = Some(match take_snapshot(&state, config) {
+                            Ok(png) => format!("snapshot saved to {}", png.display()),
+                            Err(e) => e,
+                        });
+                    },
+                    Some(action) => if let Some(dir) = action.direction() {
+                        tokio::spawn(key_handler(dir, socket.clone(), game_state.clone(), deliveries.clone()));
+                    },
+                    None => {}
+                }
+            },
+            _ = redraw.tick() => {
+                let state = game_state.lock().await.clone();
+                tracker.observe(&state);
+                toasts.update();
+                draw(&state, config, my_id, status.as_deref(), &toasts);
+            },
+        }
+    }
+
+    drop(terminal_guard);
+
+    leave(socket, game_state.clone(), deliveries, config).await;
+
+    record_store.append(tracker.finish(&game_state.lock().await.clone()));
+    record_store.save();
+}
+
+// Puts the terminal back however the loop ends, so a panic does not leave the shell in raw mode
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> TerminalGuard {
+        terminal::enable_raw_mode().expect("failed to switch the terminal to raw mode");
+        let guard = TerminalGuard;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))
+            .expect("failed to prepare the terminal");
+        guard
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn to_piston_key(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Esc => Key::Escape,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Enter => Key::Return,
+        KeyCode::F(12) => Key::F12,
+        // piston names letters by their lower case codes
+        KeyCode::Char(c) if c.is_ascii() => Key::from(c.to_ascii_lowercase() as u32),
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn to_term_color(color: Color) -> TermColor {
+    TermColor::Rgb {
+        r: (color[0] * 255.0) as u8,
+        g: (color[1] * 255.0) as u8,
+        b: (color[2] * 255.0) as u8,
+    }
+}
+
+// Paints the cells whose centres the rectangle covers, so details smaller than a cell are left out
+fn fill(board: &mut [Vec<Option<Color>>], color: Color, x: f64, y: f64, width: f64, height: f64) {
+    let covered = |start: f64, size: f64| (start - 0.5).ceil().max(0.0) as usize..(start + size - 0.5).ceil().max(0.0) as usize;
+    let (rows, columns) = (covered(y, height), covered(x, width));
+    for row in board.iter_mut().take(rows.end).skip(rows.start) {
+        for cell in row.iter_mut().take(columns.end).skip(columns.start) {
+            *cell = Some(color);
+        }
+    }
+}
+
+fn draw(state: &GameState, config: &GameConfig, my_id: i32, status: Option<&str>, toasts: &Toasts) {
+    let (width, height) = (config.get_width(), config.get_height());
+    let mut board: Vec<Vec<Option<Color>>> = vec![vec![None; width as usize]; height as usize];
+
+    // Reuse the game's own drawing and rasterize it into terminal cells, text is shown below the board
+    let mut recorder = RecordingRenderer::default();
+    state.draw(&mut recorder, config);
+    for call in recorder.calls {
+        match call {
+            DrawCall::Cell { color, x, y } => fill(&mut board, color, x as f64, y as f64, 1.0, 1.0),
+            DrawCall::Rect { color, x, y, width, height } => fill(&mut board, color, x, y, width, height),
+            DrawCall::Text { .. } => {}
+        }
+    }
+
+    // every frame overwrites the previous one in place, clearing the screen first would flicker
+    let mut out = stdout();
+    for (y, row) in board.iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, y as u16)).expect("failed to draw");
+        for cell in row {
+            // two characters per cell keep the board roughly square
+            match cell {
+                Some(color) => queue!(out, SetForegroundColor(to_term_color(*color)), Print("██")),
+                None => queue!(out, Print("  ")),
+            }.expect("failed to draw");
+        }
+        queue!(out, ResetColor).expect("failed to draw");
+    }
+
+    for (y, player) in (height as u16 + 1..).zip(state.get_players().get_players()) {
+        let line = format!("{}({:?}), score: {}", player.get_name(), player.get_role(), player.get_score());
+        let line = if player.get_id() == my_id { line.bold() } else { line.stylize() };
+        queue!(out, cursor::MoveTo(0, y), SetForegroundColor(to_term_color(state.player_color(player.get_id()))), Print("██ "), ResetColor, Print(line),
+            terminal::Clear(terminal::ClearType::UntilNewLine))
+            .expect("failed to draw");
+    }
+    // players who left the game take their lines with them
+    queue!(out, cursor::MoveToNextLine(1), terminal::Clear(terminal::ClearType::FromCursorDown)).expect("failed to draw");
+    if let Some(status) = status {
+        queue!(out, Print(status), cursor::MoveToNextLine(1)).expect("failed to draw");
+    }
+    for notice in toasts.shown() {
+        match notice {
+            Notice::Error(text) => queue!(out, Print(text.as_str().red()), cursor::MoveToNextLine(1)),
+            Notice::Info(text) => queue!(out, Print(text), cursor::MoveToNextLine(1)),
+        }.expect("failed to draw");
+    }
+    out.flush().expect("failed to draw");
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+use piston_window::*;
+
+use crate::BACK_COLOR;
+use crate::bots::Difficulty;
+use crate::camera::Camera;
+use crate::controls::{Action, KeyBindings};
+use crate::drawing::{cell_size_for, draw_side_panel, load_glyphs, side_panel_overflow, PistonRenderer};
+use crate::dto::{Direction, GameConfig, GameState};
+use crate::game_over::DeathWatch;
+use crate::interpolation::Interpolator;
+use crate::notifications::{Notice, Toasts};
+use crate::records::{MatchTracker, RecordStore};
+use crate::snapshot::take_snapshot;
+
+// Where the game shown in the window is played: in the window itself or by a master over the network
+pub(crate) trait Session {
+    fn my_id(&self) -> i32;
+
+    // The state to draw, a game without networking plays its turns from here
+    async fn state(&mut self) -> GameState;
+
+    // Steers the hot-seat players sharing the keyboard
+    async fn steer_seats(&mut self, key: Key);
+
+    async fn steer(&mut self, dir: Direction);
+
+    async fn add_robot(&mut self, difficulty: Difficulty);
+
+    async fn remove_robot(&mut self);
+
+    fn can_rejoin(&self) -> bool;
+
+    async fn rejoin(&mut self);
+
+    async fn leave(&mut self);
+}
+
+// Handles the keys and draws the game until the window closes, then saves the results
+pub(crate) async fn run_window(
+    mut window: PistonWindow,
+    mut session: impl Session,
+    config: &GameConfig,
+    bindings: KeyBindings,
+    font: Option<&Path>,
+    mut toasts: Toasts,
+) {
+    let my_id = session.my_id();
+    // Create a Glyphs object for rendering text
+    let mut glyphs = load_glyphs(&mut window, font);
+
+    let (mut record_store, records_error) = RecordStore::load();
+    let mut show_high_scores = false;
+    let mut state = session.state().await;
+    let mut tracker = MatchTracker::new(state.generate_announcement(config.clone()).get_game_name().to_string());
+    let mut death_watch = DeathWatch::default();
+    let mut panel_scroll: usize = 0;
+    let mut interpolator = Interpolator::new(config);
+    let mut camera = Camera::default();
+    let mut window_size: [f64; 2] = window.size().into();
+    if let Some(e) = records_error {
+        eprintln!("{}", e);
+        toasts.show(Notice::Error(e));
+    }
+
+    while let Some(event) = window.next() {
+        if let Some(resize) = event.resize_args() {
+            window_size = resize.window_size;
+        }
+
+        // Catch the events of the keyboard
+        if let Some(Button::Keyboard(key)) = event.press_args() {
+            session.steer_seats(key).await;
+            match bindings.action(key) {
+                Some(Action::HighScores) => show_high_scores = !show_high_scores,
+                Some(Action::ScrollPanelUp) => panel_scroll = panel_scroll.saturating_sub(1),
+                Some(Action::ScrollPanelDown) => {
+                    let overflow = side_panel_overflow(&camera.layout(config), &state, camera.panel_reserve(config));
+                    panel_scroll = (panel_scroll + 1).min(overflow);
+                },
+                Some(Action::ToggleCamera) => camera.toggle(),
+                Some(Action::Leave) => window.set_should_close(true),
+                Some(Action::Screenshot) => match take_snapshot(&state, config) {
+                    Ok(png) => println!("snapshot saved to {}", png.display()),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        toasts.show(Notice::Error(e));
+                    },
+                },
+                Some(Action::RemoveRobot) => session.remove_robot().await,
+                Some(Action::Spectate) => death_watch.spectate(),
+                Some(Action::Rejoin) if session.can_rejoin() && death_watch.is_game_over() => session.rejoin().await,
+                Some(action) => if let Some(dir) = action.direction() {
+                    session.steer(dir).await;
+                } else if let Some(difficulty) = action.robot_difficulty() {
+                    session.add_robot(difficulty).await;
+                },
+                // Ignore other keys
+                None => {}
+            }
+        }
+
+        state = session.state().await;
+        tracker.observe(&state);
+        interpolator.observe(&state);
+        death_watch.observe(&state, config, my_id, &tracker);
+        camera.follow(&interpolator, &state, my_id, config);
+        toasts.update();
+        let layout = camera.layout(config);
+        let can_rejoin = session.can_rejoin();
+
+        // Draw all of them
+        window.draw_2d(&event, |c, g, device| {
+            clear(BACK_COLOR, g);
+            {
+                let mut r = PistonRenderer::new(&c, g, glyphs.as_mut(), cell_size_for(&layout, window_size));
+
+                // Draw the main game content
+                camera.draw(&state, &interpolator, &mut r, config);
+
+                draw_side_panel(&mut r, &layout, &state, my_id, panel_scroll, camera.panel_reserve(config));
+                camera.draw_minimap(&mut r, &state, config);
+                toasts.draw(&mut r, layout.get_width() as f64);
+
+                death_watch.draw(&mut r, &bindings, can_rejoin, layout.get_width() as f64, layout.get_height() as f64);
+
+                if show_high_scores {
+                    record_store.draw(&mut r, layout.get_width() as f64 * 1.5, layout.get_height() as f64);
+                }
+            }
+            if let Some(glyphs) = &mut glyphs {
+                glyphs.factory.encoder.flush(device);
+            }
+        });
+    }
+
+    session.leave().await;
+
+    record_store.append(tracker.finish(&state));
+    record_store.save();
+}